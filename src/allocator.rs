@@ -0,0 +1,166 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// The system allocator, counting every allocation made through it. Once a
+/// memory limit is set it also keeps the bytes still in use, failing any
+/// allocation that would take them past the limit, which aborts the process.
+/// Built with the `profile-alloc` feature it keeps the bytes in use all along,
+/// and while profiling the bytes asked for and the most bytes in use at once.
+///
+/// It's installed in every build, as the sandbox's child process is this same
+/// binary and has to be able to enforce its limit. Without a limit or the
+/// feature that costs each allocation an atomic add for the count `bench`
+/// reports and a load to see there's no limit, and each free just the load.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+/// the most bytes that may be in use at once, usize::MAX for no limit
+static LIMIT: AtomicUsize = AtomicUsize::new(usize::MAX);
+static PROFILING: AtomicBool = AtomicBool::new(false);
+static BYTES_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// what a piece of code did with the heap
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Profile {
+    /// the most bytes in use at once, over what was in use beforehand
+    pub peak_bytes: usize,
+    /// allocations, including reallocations
+    pub allocations: usize,
+    /// bytes asked for, counting the whole new size of a reallocation
+    pub bytes_allocated: usize,
+}
+
+/// whether the bytes in use are kept, which a plain build leaves to the sandbox
+fn tracking() -> bool {
+    cfg!(feature = "profile-alloc") || LIMIT.load(Ordering::Relaxed) != usize::MAX
+}
+
+fn profiling() -> bool {
+    cfg!(feature = "profile-alloc") && PROFILING.load(Ordering::Relaxed)
+}
+
+fn count_allocation(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    if profiling() {
+        BYTES_ALLOCATED.fetch_add(size, Ordering::Relaxed);
+    }
+}
+
+/// takes `size` more bytes into use, unless that would go past the limit
+fn reserve(size: usize) -> bool {
+    if !tracking() {
+        return true;
+    }
+    let live = LIVE_BYTES
+        .fetch_add(size, Ordering::Relaxed)
+        .saturating_add(size);
+    if live > LIMIT.load(Ordering::Relaxed) {
+        LIVE_BYTES.fetch_sub(size, Ordering::Relaxed);
+        return false;
+    }
+    if profiling() {
+        PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+    }
+    true
+}
+
+/// Gives back `size` bytes. Bytes allocated before a limit was set weren't
+/// counted, so freeing them makes up for others, never going below 0.
+fn release(size: usize) {
+    if tracking() {
+        let _ = LIVE_BYTES.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |live| {
+            Some(live.saturating_sub(size))
+        });
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation(layout.size());
+        if !reserve(layout.size()) {
+            return std::ptr::null_mut();
+        }
+        let ptr = System.alloc(layout);
+        if ptr.is_null() {
+            release(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        release(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation(new_size);
+        let grown = new_size.saturating_sub(layout.size());
+        if !reserve(grown) {
+            return std::ptr::null_mut();
+        }
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        match new_ptr.is_null() {
+            true => release(grown),
+            false => release(layout.size().saturating_sub(new_size)),
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// how many allocations (including reallocations) have been made so far
+pub fn allocations() -> usize {
+    ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+/// Fails any allocation that would take the heap in use past `bytes`, counted
+/// from here on. Anything allocated before then and freed later is taken off
+/// the count all the same, allowing that much more, so a process should set
+/// its limit before it allocates much. Rust aborts on a failed allocation, so
+/// this is for a process that only solves.
+pub fn limit_memory(bytes: usize) {
+    LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+/// Runs `f` with the heap profiled, giving back what it returned and what it
+/// did with the heap. Allocations from other threads meanwhile count too, and
+/// without the `profile-alloc` feature only the allocations are counted.
+pub fn profile<T>(f: impl FnOnce() -> T) -> (T, Profile) {
+    let live = LIVE_BYTES.load(Ordering::Relaxed);
+    PEAK_BYTES.store(live, Ordering::Relaxed);
+    let allocations_before = allocations();
+    let bytes_before = BYTES_ALLOCATED.load(Ordering::Relaxed);
+    PROFILING.store(true, Ordering::Relaxed);
+    let value = f();
+    PROFILING.store(false, Ordering::Relaxed);
+    let profile = Profile {
+        peak_bytes: PEAK_BYTES.load(Ordering::Relaxed).saturating_sub(live),
+        allocations: allocations() - allocations_before,
+        bytes_allocated: BYTES_ALLOCATED.load(Ordering::Relaxed) - bytes_before,
+    };
+    (value, profile)
+}
+
+#[cfg(all(test, feature = "profile-alloc"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile() {
+        let (v, profile) = profile(|| {
+            let mut v = Vec::<u8>::with_capacity(1000);
+            v.reserve_exact(3000);
+            drop(Vec::<u8>::with_capacity(500));
+            v
+        });
+        assert_eq!(v.capacity(), 3000);
+        // other tests allocate on their own threads meanwhile, so these are lower bounds
+        assert!(profile.allocations >= 3);
+        assert!(profile.bytes_allocated >= 3500);
+        assert!(profile.peak_bytes >= 3000);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::allocator::{self, Profile};
+use advent_of_code_2023::days::*;
+use std::fmt;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+pub struct BenchResult {
+    pub day: u32,
+    pub parse: Duration,
+    pub parse_allocations: usize,
+    pub part_a: Duration,
+    pub part_b: Duration,
+    /// each step's heap use, when asked for
+    pub memory: Option<[Profile; 3]>,
+}
+
+/// a number of bytes in the largest unit it's at least one of
+fn format_bytes(bytes: usize) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", size, units[unit]),
+    }
+}
+
+impl fmt::Display for BenchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(memory) = &self.memory {
+            writeln!(f, "day {}", self.day)?;
+            let steps = [
+                ("parse", self.parse),
+                ("part a", self.part_a),
+                ("part b", self.part_b),
+            ];
+            for ((step, time), profile) in steps.iter().zip(memory) {
+                writeln!(
+                    f,
+                    "  {:<6} {:>12?}  {:>8} allocs  {:>10} allocated  {:>10} peak",
+                    step,
+                    time,
+                    profile.allocations,
+                    format_bytes(profile.bytes_allocated),
+                    format_bytes(profile.peak_bytes)
+                )?;
+            }
+            return Ok(());
+        }
+        write!(
+            f,
+            "day {:<2}  parse {:>12?} ({:>7} allocs)  part a {:>12?}  part b {:>12?}",
+            self.day, self.parse, self.parse_allocations, self.part_a, self.part_b
+        )
+    }
+}
+
+/// mean wall clock time of `iterations` calls to `f`
+fn time_mean<T>(iterations: u32, mut f: impl FnMut() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    start.elapsed() / iterations.max(1)
+}
+
+/// how many allocations (including reallocations) a single call to `f` makes
+fn count_allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = allocator::allocations();
+    black_box(f());
+    allocator::allocations() - before
+}
+
+/// what a single call to `f` did with the heap
+fn profile_memory<T>(f: impl FnOnce() -> T) -> Profile {
+    let (value, profile) = allocator::profile(f);
+    black_box(value);
+    profile
+}
+
+macro_rules! bench_day {
+    ($day_num:expr, $day:ident, $input:expr, $iterations:expr, $memory:expr) => {{
+        let parsed = $day::parse($input);
+        BenchResult {
+            day: $day_num,
+            parse: time_mean($iterations, || $day::parse($input)),
+            parse_allocations: count_allocations(|| $day::parse($input)),
+            part_a: time_mean($iterations, || $day::solve_part_a(&parsed)),
+            part_b: time_mean($iterations, || $day::solve_part_b(&parsed)),
+            memory: $memory.then(|| {
+                [
+                    profile_memory(|| $day::parse($input)),
+                    profile_memory(|| $day::solve_part_a(&parsed)),
+                    profile_memory(|| $day::solve_part_b(&parsed)),
+                ]
+            }),
+        }
+    }};
+}
+
+/// Times parsing and both parts of a day separately, so changes to the
+/// parsers show up on their own, and with `memory` profiles their heap use.
+pub fn bench(day: u32, input: &str, iterations: u32, memory: bool) -> BenchResult {
+    match day {
+        1 => bench_day!(1, day_1, input, iterations, memory),
+        2 => bench_day!(2, day_2, input, iterations, memory),
+        3 => bench_day!(3, day_3, input, iterations, memory),
+        4 => bench_day!(4, day_4, input, iterations, memory),
+        5 => bench_day!(5, day_5, input, iterations, memory),
+        6 => bench_day!(6, day_6, input, iterations, memory),
+        7 => bench_day!(7, day_7, input, iterations, memory),
+        8 => bench_day!(8, day_8, input, iterations, memory),
+        9 => bench_day!(9, day_9, input, iterations, memory),
+        _ => panic!("Unrecognised day [{}]", day),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
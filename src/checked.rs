@@ -0,0 +1,98 @@
+use num::traits::{CheckedAdd, CheckedMul, CheckedSub, One, Zero};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Unwraps the result of a checked operation, panicking with the day and the
+/// step that overflowed. Used for every sum or product that builds an answer.
+#[inline]
+pub fn expect<T>(value: Option<T>, day: u32, step: &str) -> T {
+    value.unwrap_or_else(|| panic!("Overflow on day {} while {}", day, step))
+}
+
+/// sums `values`, panicking with the day and step on overflow
+pub fn sum<T: CheckedAdd + Zero>(values: impl IntoIterator<Item = T>, day: u32, step: &str) -> T {
+    values.into_iter().fold(T::zero(), |acc, value| {
+        expect(acc.checked_add(&value), day, step)
+    })
+}
+
+/// multiplies `values`, panicking with the day and step on overflow
+pub fn product<T: CheckedMul + One>(
+    values: impl IntoIterator<Item = T>,
+    day: u32,
+    step: &str,
+) -> T {
+    values.into_iter().fold(T::one(), |acc, value| {
+        expect(acc.checked_mul(&value), day, step)
+    })
+}
+
+/// `a + b` for an intermediate step, only named in an overflow panic when
+/// `--checked` is on and otherwise as the build's own arithmetic checks it
+#[inline]
+pub fn add<T: CheckedAdd>(a: T, b: T, day: u32, step: &str) -> T {
+    match is_enabled() {
+        true => expect(a.checked_add(&b), day, step),
+        false => a + b,
+    }
+}
+
+/// `a - b` for an intermediate step, like [`add`]
+#[inline]
+pub fn sub<T: CheckedSub>(a: T, b: T, day: u32, step: &str) -> T {
+    match is_enabled() {
+        true => expect(a.checked_sub(&b), day, step),
+        false => a - b,
+    }
+}
+
+/// `a * b` for an intermediate step, like [`add`]
+#[inline]
+pub fn mul<T: CheckedMul>(a: T, b: T, day: u32, step: &str) -> T {
+    match is_enabled() {
+        true => expect(a.checked_mul(&b), day, step),
+        false => a * b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic;
+
+    #[test]
+    fn test_checked_mode() {
+        let _flags = crate::lock_flags();
+        assert_eq!(add(u32::MAX - 1, 1, 1, "testing"), u32::MAX);
+        assert_eq!(product([2u64, 3, 7], 1, "testing"), 42);
+
+        enable();
+        let result = panic::catch_unwind(|| add(u32::MAX, 1, 1, "testing"));
+        disable();
+
+        let message = *result.unwrap_err().downcast::<String>().unwrap();
+        assert_eq!(message, "Overflow on day 1 while testing");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "attempt to add with overflow")]
+    fn test_unchecked_mode_still_panics_in_debug() {
+        let _flags = crate::lock_flags();
+        add(u32::MAX, 1, 1, "testing");
+    }
+}
@@ -0,0 +1,469 @@
+use crate::config::{self, Overrides};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+
+/// Run Advent of Code 2023.
+#[derive(Parser)]
+#[command(name = "advent-of-code-2023", version)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// settings every subcommand takes, most of them overriding the config
+#[derive(Args)]
+pub struct GlobalArgs {
+    /// Config file to read settings from (aoc.toml, if there is one)
+    #[arg(long, global = true, default_value = config::DEFAULT_PATH)]
+    pub config: String,
+    /// Puzzle year
+    #[arg(long, global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub year: Option<u32>,
+    /// Directory of day_#.txt inputs
+    #[arg(long, global = true)]
+    pub input_root: Option<String>,
+    /// Whose inputs to use, from <INPUT_ROOT>/<PROFILE>/day_#.txt
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+    /// Where the session token comes from [env:VARIABLE, file:PATH]
+    #[arg(long, global = true)]
+    pub session: Option<String>,
+    /// Format of results and --explain trace events
+    #[arg(long, global = true, value_enum, alias = "explain-format")]
+    pub output: Option<Output>,
+    /// Let solvers split independent work (games, ghosts, sequences) across threads
+    #[arg(long, global = true)]
+    pub parallel: bool,
+    /// Check every step of the solvers' arithmetic for overflow, not just the answer
+    #[arg(long, global = true)]
+    pub checked: bool,
+}
+
+impl GlobalArgs {
+    pub fn overrides(&self) -> Overrides {
+        Overrides {
+            year: self.year,
+            input_root: self.input_root.clone(),
+            profile: self.profile.clone(),
+            session: self.session.clone(),
+            output: self.output.map(|output| output.name().to_string()),
+            iterations: None,
+            timeout: None,
+            memory_limit: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Output {
+    Text,
+    Json,
+}
+
+impl Output {
+    fn name(&self) -> &'static str {
+        match self {
+            Output::Text => "text",
+            Output::Json => "json",
+        }
+    }
+}
+
+/// the parts `run` solves, both by default
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Parts {
+    A,
+    B,
+    Both,
+}
+
+impl Parts {
+    pub fn chars(&self) -> &'static [char] {
+        match self {
+            Parts::A => &['a'],
+            Parts::B => &['b'],
+            Parts::Both => &['a', 'b'],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Parts::A => "a",
+            Parts::B => "b",
+            Parts::Both => "both",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Part {
+    A,
+    B,
+}
+
+impl Part {
+    pub fn as_char(&self) -> char {
+        match self {
+            Part::A => 'a',
+            Part::B => 'b',
+        }
+    }
+}
+
+/// the puzzle day, from 1 to 25
+#[derive(Args)]
+pub struct DayArg {
+    /// Day of the puzzle
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..=25))]
+    pub day: u32,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Solve a day, both parts by default
+    Run(RunArgs),
+    /// Solve both parts of every implemented day
+    All {
+        /// How many days and parts to run at once, 0 uses every core
+        #[arg(short, long, default_value_t = 0)]
+        jobs: usize,
+        /// Run every profile's inputs, checking each answer against the profile's in the ledger
+        #[arg(long)]
+        profiles: bool,
+        /// Seconds each part may run before it's stopped (defaults to the config)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        timeout: Option<u32>,
+        /// MiB of heap each part may use before it's stopped (defaults to the config)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        memory_limit: Option<u32>,
+    },
+    /// Time parsing and both parts of a day separately
+    Bench {
+        #[command(flatten)]
+        day: DayArg,
+        /// How many times to run each step (defaults to the config)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        iterations: Option<u32>,
+        /// Also profile each step's heap use: allocations, bytes allocated and peak (needs the profile-alloc feature)
+        #[arg(long)]
+        memory: bool,
+    },
+    /// Download a day's input using the session token
+    Fetch {
+        #[command(flatten)]
+        day: DayArg,
+        /// Replace an input that's already been downloaded
+        #[arg(long)]
+        force: bool,
+    },
+    /// Send an answer to adventofcode.com, solving for it if none is given
+    Submit {
+        #[command(flatten)]
+        day: DayArg,
+        /// Part of the puzzle
+        #[arg(short, long, value_enum)]
+        part: Part,
+        /// The answer to send instead of the solver's
+        answer: Option<String>,
+    },
+    /// Create the files for a new day from the template
+    New {
+        #[command(flatten)]
+        day: DayArg,
+    },
+    /// Check a day's input is in the shape the solver expects
+    Validate {
+        #[command(flatten)]
+        day: DayArg,
+        /// Input file to check, `-` for stdin (defaults to the day's input in the config)
+        #[arg(short, long)]
+        input: Option<String>,
+    },
+    /// List every day with whether it's solved and has an input
+    List,
+    /// Solve a part of the input on stdin within a memory limit, for `all` to run limited parts in
+    #[command(hide = true)]
+    Solve {
+        #[command(flatten)]
+        day: DayArg,
+        #[arg(short, long, value_enum)]
+        part: Part,
+        /// MiB of heap the solver may use
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        memory_limit: Option<u32>,
+    },
+    /// Check every day's answers to its example, built into the binary, exiting with 1 on any mismatch
+    Selftest,
+    /// Day 2: the games each bag can play and how many bags can play each game
+    Bags {
+        #[command(flatten)]
+        bags: BagArgs,
+        /// Input file to read the games from, `-` for stdin
+        #[arg(short, long)]
+        input: Option<String>,
+    },
+    /// Print a random but valid input for a day
+    Generate {
+        #[command(flatten)]
+        day: DayArg,
+        /// Size of the input (lines, grid width, ...)
+        #[arg(long)]
+        size: Option<usize>,
+        /// Random seed
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Most winning numbers a day 4 card may match
+        #[arg(long)]
+        matches: Option<usize>,
+        /// Number of ghosts in a day 8 network
+        #[arg(long)]
+        ghosts: Option<usize>,
+    },
+    /// Draw a day's input
+    Visualise {
+        #[command(flatten)]
+        day: DayArg,
+        /// Output format [ansi, svg] for day 3, [dot] for day 8
+        #[arg(long, default_value = "")]
+        format: String,
+    },
+    /// Re-run a day's tests and solver whenever its source, input or example changes
+    Watch {
+        #[command(flatten)]
+        day: DayArg,
+        /// Part of the puzzle, or both
+        #[arg(short, long, value_enum, default_value_t = Parts::Both)]
+        part: Parts,
+        /// How often to check the files for changes, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        interval: u64,
+    },
+    /// Show how each day's answers and runtimes have changed across runs
+    History {
+        /// Only show this day
+        #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..=25))]
+        day: Option<u32>,
+        /// Only show this part
+        #[arg(short, long, value_enum)]
+        part: Option<Part>,
+        /// Only show runs whose answer changed for the same input, exiting with 1 if there are any
+        #[arg(long)]
+        changes: bool,
+    },
+    /// Work with the config file
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Print a shell completion script
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the settings a run would use once the config and flags are combined
+    Show,
+}
+
+#[derive(Args)]
+pub struct BagArgs {
+    /// The cubes in a bag, e.g. "12 red, 13 green, 14 blue" (bags: may be repeated)
+    #[arg(long = "bag")]
+    pub bags: Vec<String>,
+    /// File of bags, one per line, read as well as any --bag
+    #[arg(long = "bags")]
+    pub bags_file: Option<String>,
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    #[command(flatten)]
+    pub day: DayArg,
+    /// Part of the puzzle, or both from a single parse of the input [default: both,
+    /// or b with --digit-words or --jokers]
+    #[arg(short, long, value_enum)]
+    pub part: Option<Parts>,
+    /// Print the trace events the solver emits on the way to its answer
+    #[arg(long)]
+    pub explain: bool,
+    /// Input file to run against, `-` for stdin (defaults to the day's input in the config)
+    #[arg(short, long)]
+    pub input: Option<String>,
+    /// Solve line by line as the input is read instead of loading it all (days 1, 2, 4, 7, 9)
+    #[arg(long)]
+    pub stream: bool,
+
+    /// File of `word=value` lines to read as numbers instead of one to nine
+    #[arg(long, help_heading = "Day 1")]
+    pub digit_words: Option<String>,
+
+    #[command(flatten, next_help_heading = "Day 2")]
+    pub bags: BagArgs,
+
+    /// Only count numbers next to this symbol (a), or gears made of it (b, defaults to *)
+    #[arg(long, help_heading = "Day 3")]
+    pub symbol: Option<char>,
+    /// How many numbers a gear touches
+    #[arg(long, help_heading = "Day 3")]
+    pub neighbours: Option<usize>,
+
+    /// How cards score [doubling, linear, table:0,1,3,...]
+    #[arg(long, help_heading = "Day 4")]
+    pub scoring: Option<String>,
+    /// Most copies any one card can have
+    #[arg(long, help_heading = "Day 4", value_parser = clap::value_parser!(u64).range(1..))]
+    pub copy_cap: Option<u64>,
+    /// Copies won past the last card go to the first cards
+    #[arg(long, help_heading = "Day 4")]
+    pub wrap_copies: bool,
+
+    /// How boats speed up [linear, linear:RATE, power:EXPONENT, exponential:BASE]
+    #[arg(long, help_heading = "Day 6")]
+    pub acceleration: Option<String>,
+    /// Fastest a boat can go
+    #[arg(long, help_heading = "Day 6")]
+    pub max_speed: Option<u64>,
+
+    /// The cards a joker may become, e.g. 23456789T (defaults to any)
+    #[arg(long, help_heading = "Day 7")]
+    pub jokers: Option<String>,
+}
+
+impl RunArgs {
+    /// the parts asked for, defaulting to part b alone for options only it takes
+    pub fn parts(&self) -> Parts {
+        match self.part {
+            Some(part) => part,
+            None if self.digit_words.is_some() || self.jokers.is_some() => Parts::B,
+            None => Parts::Both,
+        }
+    }
+
+    /// the day's options given, as they'd be written on the command line
+    pub fn day_options(&self) -> String {
+        let mut options = Vec::new();
+        let mut add = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                options.push(format!("--{} {}", name, value).trim_end().to_string());
+            }
+        };
+        add("digit-words", self.digit_words.clone());
+        for bag in &self.bags.bags {
+            add("bag", Some(bag.clone()));
+        }
+        add("bags", self.bags.bags_file.clone());
+        add("symbol", self.symbol.map(|s| s.to_string()));
+        add("neighbours", self.neighbours.map(|n| n.to_string()));
+        add("scoring", self.scoring.clone());
+        add("copy-cap", self.copy_cap.map(|c| c.to_string()));
+        add("wrap-copies", self.wrap_copies.then(String::new));
+        add("acceleration", self.acceleration.clone());
+        add("max-speed", self.max_speed.map(|s| s.to_string()));
+        add("jokers", self.jokers.clone());
+        options.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+    use clap::CommandFactory;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(["advent-of-code-2023"].iter().chain(args))
+    }
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let error_kind = |args: &[&str]| parse(args).err().map(|e| e.kind());
+        assert_eq!(
+            error_kind(&["run", "-d", "26", "-p", "a"]),
+            Some(ErrorKind::ValueValidation)
+        );
+        assert_eq!(
+            error_kind(&["run", "-d", "1", "-p", "c"]),
+            Some(ErrorKind::InvalidValue)
+        );
+        assert_eq!(
+            error_kind(&["submit", "-d", "1"]),
+            Some(ErrorKind::MissingRequiredArgument)
+        );
+        assert_eq!(error_kind(&["launch"]), Some(ErrorKind::InvalidSubcommand));
+        assert_eq!(
+            error_kind(&["run", "-d", "4", "--copy-cap", "0"]),
+            Some(ErrorKind::ValueValidation)
+        );
+        assert_eq!(
+            error_kind(&["bench", "-d", "1", "--iterations", "0"]),
+            Some(ErrorKind::ValueValidation)
+        );
+        assert_eq!(
+            error_kind(&["all", "--timeout", "0"]),
+            Some(ErrorKind::ValueValidation)
+        );
+        assert_eq!(
+            error_kind(&["all", "--memory-limit", "0"]),
+            Some(ErrorKind::ValueValidation)
+        );
+    }
+
+    #[test]
+    fn test_default_parts() {
+        let parts = |args: &[&str]| match parse(args).unwrap().command {
+            Command::Run(run) => run.parts().chars(),
+            _ => panic!("expected the run command"),
+        };
+        assert_eq!(parts(&["run", "-d", "1"]), &['a', 'b']);
+        assert_eq!(parts(&["run", "-d", "1", "--digit-words", "f"]), &['b']);
+        assert_eq!(parts(&["run", "-d", "7", "--jokers", "23456789T"]), &['b']);
+        assert_eq!(
+            parts(&["run", "-d", "7", "-p", "a", "--jokers", "J"]),
+            &['a']
+        );
+    }
+
+    #[test]
+    fn test_validate_input() {
+        match parse(&["validate", "-d", "7", "--input", "pasted.txt"])
+            .unwrap()
+            .command
+        {
+            Command::Validate { input, .. } => assert_eq!(input.as_deref(), Some("pasted.txt")),
+            _ => panic!("expected the validate command"),
+        }
+    }
+
+    #[test]
+    fn test_global_flags() {
+        let cli = parse(&[
+            "run",
+            "-d",
+            "2",
+            "-p",
+            "b",
+            "--bag",
+            "1 red",
+            "--explain-format",
+            "json",
+        ]);
+        let cli = cli.unwrap();
+        assert_eq!(cli.global.overrides().output, Some("json".to_string()));
+        match cli.command {
+            Command::Run(run) => {
+                assert!(run.parts() == Parts::B);
+                assert_eq!(run.bags.bags, vec!["1 red"]);
+                assert_eq!(run.day_options(), "--bag 1 red");
+            }
+            _ => panic!("expected the run command"),
+        }
+    }
+}
@@ -0,0 +1,606 @@
+use crate::{history, ledger};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+use std::{env, fs};
+use toml::{Table, Value};
+
+/// the config file read from the working directory unless `--config` says otherwise
+pub const DEFAULT_PATH: &str = "aoc.toml";
+/// the directory under the input root holding the puzzles' example inputs
+const EXAMPLES_DIR: &str = "test";
+
+/// where the adventofcode.com session token is read from
+#[derive(Clone, Debug, PartialEq)]
+pub enum SessionSource {
+    /// an environment variable
+    Env(String),
+    /// a file holding just the token
+    File(String),
+}
+
+/// reads "env:AOC_SESSION" or "file:~/.config/aoc/session"
+impl FromStr for SessionSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(":") {
+            Some(("env", name)) if !name.is_empty() => Ok(SessionSource::Env(name.to_string())),
+            Some(("file", path)) if !path.is_empty() => Ok(SessionSource::File(path.to_string())),
+            _ => Err(format!(
+                "session must be env:VARIABLE or file:PATH, found [{}]",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SessionSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionSource::Env(name) => write!(f, "env:{}", name),
+            SessionSource::File(path) => write!(f, "file:{}", path),
+        }
+    }
+}
+
+/// settings for a single day, each falling back to the top level ones
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DayConfig {
+    /// parts `all` leaves out, e.g. a slow brute force
+    pub skip: Vec<char>,
+    /// the day's input file, instead of the one under the input root
+    pub input: Option<String>,
+    /// bench iterations for the day
+    pub iterations: Option<u32>,
+    /// seconds `all` gives each of the day's parts
+    pub timeout: Option<u32>,
+    /// MiB of heap `all` gives each of the day's parts
+    pub memory_limit: Option<u32>,
+    /// day 2: the bags played when no `--bag` or `--bags` is given
+    pub bags: Vec<String>,
+}
+
+/// The effective settings of a run: the defaults, then `aoc.toml`, then any
+/// flags given on the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub year: u32,
+    /// directory holding `day_N.txt` inputs
+    pub input_root: String,
+    /// whose inputs to use, read from `<input_root>/<profile>/day_N.txt`
+    pub profile: Option<String>,
+    pub session: SessionSource,
+    /// how results and --explain trace events are printed [text, json]
+    pub output: String,
+    /// bench iterations per step
+    pub iterations: u32,
+    /// seconds `all` gives each part before stopping it, None for no limit
+    pub timeout: Option<u32>,
+    /// MiB of heap `all` gives each part before stopping it, None for no limit
+    pub memory_limit: Option<u32>,
+    /// file of known right answers
+    pub ledger: String,
+    /// file every run's answers are added to
+    pub history: String,
+    pub days: BTreeMap<u32, DayConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            year: 2023,
+            input_root: "inputs".to_string(),
+            profile: None,
+            session: SessionSource::Env("AOC_SESSION".to_string()),
+            output: "text".to_string(),
+            iterations: 10,
+            timeout: None,
+            memory_limit: None,
+            ledger: ledger::DEFAULT_PATH.to_string(),
+            history: history::DEFAULT_PATH.to_string(),
+            days: BTreeMap::new(),
+        }
+    }
+}
+
+fn expect_str<'v>(value: &'v Value, key: &str) -> Result<&'v str, String> {
+    value
+        .as_str()
+        .ok_or_else(|| format!("{} must be a string, found {}", key, value))
+}
+
+/// a profile name, which can't start with a digit as the ledger reads those keys as days
+fn expect_profile(profile: &str) -> Result<String, String> {
+    match profile.starts_with(|c: char| c.is_ascii_digit()) {
+        true => Err(format!(
+            "profile [{}] can't start with a digit, as days do",
+            profile
+        )),
+        false => Ok(profile.to_string()),
+    }
+}
+
+/// a year, iteration count or limit, none of which can be 0
+fn expect_u32(value: &Value, key: &str) -> Result<u32, String> {
+    value
+        .as_integer()
+        .and_then(|i| u32::try_from(i).ok())
+        .filter(|&n| n > 0)
+        .ok_or_else(|| format!("{} must be a positive number, found {}", key, value))
+}
+
+fn expect_output(output: &str) -> Result<String, String> {
+    match output {
+        "text" | "json" => Ok(output.to_string()),
+        _ => Err(format!("output must be text or json, found [{}]", output)),
+    }
+}
+
+fn parse_day(day: &str, value: &Value) -> Result<(u32, DayConfig), String> {
+    let day_num = day
+        .parse::<u32>()
+        .ok()
+        .filter(|d| (1..=25).contains(d))
+        .ok_or_else(|| format!("days.{} is not a day between 1 and 25", day))?;
+    let table = value
+        .as_table()
+        .ok_or_else(|| format!("days.{} must be a table", day))?;
+
+    let mut day_config = DayConfig::default();
+    for (key, value) in table {
+        let key_path = format!("days.{}.{}", day, key);
+        match key.as_str() {
+            "skip" => {
+                let parts = value
+                    .as_array()
+                    .ok_or_else(|| format!("{} must be a list of parts", key_path))?;
+                for part in parts {
+                    match expect_str(part, &key_path)? {
+                        "a" => day_config.skip.push('a'),
+                        "b" => day_config.skip.push('b'),
+                        p => return Err(format!("{} has unknown part [{}]", key_path, p)),
+                    }
+                }
+            }
+            "input" => day_config.input = Some(expect_str(value, &key_path)?.to_string()),
+            "iterations" => day_config.iterations = Some(expect_u32(value, &key_path)?),
+            "timeout" => day_config.timeout = Some(expect_u32(value, &key_path)?),
+            "memory_limit" => day_config.memory_limit = Some(expect_u32(value, &key_path)?),
+            "bags" if day_num != 2 => return Err(format!("{} only applies to day 2", key_path)),
+            "bags" => {
+                let bags = value
+                    .as_array()
+                    .ok_or_else(|| format!("{} must be a list of bags", key_path))?;
+                for bag in bags {
+                    day_config
+                        .bags
+                        .push(expect_str(bag, &key_path)?.to_string());
+                }
+            }
+            _ => return Err(format!("unknown setting {}", key_path)),
+        }
+    }
+    Ok((day_num, day_config))
+}
+
+impl FromStr for Config {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let table = s.parse::<Table>().map_err(|e| e.to_string())?;
+        let mut config = Config::default();
+        for (key, value) in &table {
+            match key.as_str() {
+                "year" => config.year = expect_u32(value, key)?,
+                "input_root" => config.input_root = expect_str(value, key)?.to_string(),
+                "profile" => config.profile = Some(expect_profile(expect_str(value, key)?)?),
+                "session" => config.session = SessionSource::from_str(expect_str(value, key)?)?,
+                "output" => config.output = expect_output(expect_str(value, key)?)?,
+                "iterations" => config.iterations = expect_u32(value, key)?,
+                "timeout" => config.timeout = Some(expect_u32(value, key)?),
+                "memory_limit" => config.memory_limit = Some(expect_u32(value, key)?),
+                "ledger" => config.ledger = expect_str(value, key)?.to_string(),
+                "history" => config.history = expect_str(value, key)?.to_string(),
+                "days" => {
+                    let days = value
+                        .as_table()
+                        .ok_or_else(|| "days must be a table".to_string())?;
+                    for (day, day_value) in days {
+                        let (day_num, day_config) = parse_day(day, day_value)?;
+                        config.days.insert(day_num, day_config);
+                    }
+                }
+                _ => return Err(format!("unknown setting {}", key)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// prints the settings back out as the TOML they could be read from
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut table = Table::new();
+        table.insert("year".to_string(), Value::from(self.year as i64));
+        table.insert(
+            "input_root".to_string(),
+            Value::from(self.input_root.clone()),
+        );
+        if let Some(profile) = &self.profile {
+            table.insert("profile".to_string(), Value::from(profile.clone()));
+        }
+        table.insert("session".to_string(), Value::from(self.session.to_string()));
+        table.insert("output".to_string(), Value::from(self.output.clone()));
+        table.insert(
+            "iterations".to_string(),
+            Value::from(self.iterations as i64),
+        );
+        if let Some(timeout) = self.timeout {
+            table.insert("timeout".to_string(), Value::from(timeout as i64));
+        }
+        if let Some(memory_limit) = self.memory_limit {
+            table.insert("memory_limit".to_string(), Value::from(memory_limit as i64));
+        }
+        table.insert("ledger".to_string(), Value::from(self.ledger.clone()));
+        table.insert("history".to_string(), Value::from(self.history.clone()));
+
+        let mut days = Table::new();
+        for (day, day_config) in &self.days {
+            let mut day_table = Table::new();
+            if !day_config.skip.is_empty() {
+                let skip = day_config.skip.iter().map(|p| Value::from(p.to_string()));
+                day_table.insert("skip".to_string(), Value::Array(skip.collect()));
+            }
+            if let Some(input) = &day_config.input {
+                day_table.insert("input".to_string(), Value::from(input.clone()));
+            }
+            if let Some(iterations) = day_config.iterations {
+                day_table.insert("iterations".to_string(), Value::from(iterations as i64));
+            }
+            if let Some(timeout) = day_config.timeout {
+                day_table.insert("timeout".to_string(), Value::from(timeout as i64));
+            }
+            if let Some(memory_limit) = day_config.memory_limit {
+                day_table.insert("memory_limit".to_string(), Value::from(memory_limit as i64));
+            }
+            if !day_config.bags.is_empty() {
+                let bags = day_config.bags.iter().map(|bag| Value::from(bag.clone()));
+                day_table.insert("bags".to_string(), Value::Array(bags.collect()));
+            }
+            days.insert(day.to_string(), Value::Table(day_table));
+        }
+        if !days.is_empty() {
+            table.insert("days".to_string(), Value::Table(days));
+        }
+        write!(f, "{}", table)
+    }
+}
+
+/// the config-file side of the command line, each left as None when its flag isn't given
+#[derive(Default)]
+pub struct Overrides {
+    pub year: Option<u32>,
+    pub input_root: Option<String>,
+    pub profile: Option<String>,
+    pub session: Option<String>,
+    pub output: Option<String>,
+    pub iterations: Option<u32>,
+    pub timeout: Option<u32>,
+    pub memory_limit: Option<u32>,
+}
+
+impl Config {
+    /// Reads the config at `path`. The default `aoc.toml` is allowed to be
+    /// missing, giving the default settings, but a file asked for by name isn't.
+    pub fn load(path: &str) -> Result<Config, String> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) if path == DEFAULT_PATH => return Ok(Config::default()),
+            Err(_) => return Err(format!("could not open config file {}", path)),
+        };
+        Config::from_str(&contents).map_err(|e| format!("invalid config file {}: {}", path, e))
+    }
+
+    /// replaces the settings given on the command line
+    pub fn apply(&mut self, overrides: &Overrides) -> Result<(), String> {
+        if let Some(year) = overrides.year {
+            self.year = year;
+        }
+        if let Some(input_root) = &overrides.input_root {
+            self.input_root = input_root.clone();
+            // an input root given on the command line is meant for every day
+            for day_config in self.days.values_mut() {
+                day_config.input = None;
+            }
+        }
+        if let Some(profile) = &overrides.profile {
+            self.set_profile(Some(expect_profile(profile)?));
+        }
+        if let Some(session) = &overrides.session {
+            self.session = SessionSource::from_str(session)?;
+        }
+        if let Some(output) = &overrides.output {
+            self.output = expect_output(output)?;
+        }
+        if let Some(iterations) = overrides.iterations {
+            self.iterations = iterations;
+            for day_config in self.days.values_mut() {
+                day_config.iterations = None;
+            }
+        }
+        if let Some(timeout) = overrides.timeout {
+            self.timeout = Some(timeout);
+            for day_config in self.days.values_mut() {
+                day_config.timeout = None;
+            }
+        }
+        if let Some(memory_limit) = overrides.memory_limit {
+            self.memory_limit = Some(memory_limit);
+            for day_config in self.days.values_mut() {
+                day_config.memory_limit = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// the adventofcode.com session token, from wherever the config says it's kept
+    pub fn session_token(&self) -> Result<String, String> {
+        let token = match &self.session {
+            SessionSource::Env(name) => env::var(name)
+                .map_err(|_| format!("no session token, set the {} environment variable", name))?,
+            SessionSource::File(path) => {
+                let path = match path.strip_prefix("~/") {
+                    Some(rest) => format!("{}/{}", env::var("HOME").unwrap_or_default(), rest),
+                    None => path.clone(),
+                };
+                fs::read_to_string(&path)
+                    .map_err(|_| format!("could not read the session token from {}", path))?
+            }
+        };
+        Ok(token.trim().to_string())
+    }
+
+    fn day(&self, day: u32) -> Option<&DayConfig> {
+        self.days.get(&day)
+    }
+
+    /// switches to another profile's inputs, where any single day's input the config gives won't be
+    pub fn set_profile(&mut self, profile: Option<String>) {
+        self.profile = profile;
+        for day_config in self.days.values_mut() {
+            day_config.input = None;
+        }
+    }
+
+    /// the directory the profile's inputs are in
+    fn input_dir(&self) -> String {
+        match &self.profile {
+            Some(profile) => format!("{}/{}", self.input_root, profile),
+            None => self.input_root.clone(),
+        }
+    }
+
+    pub fn input_path(&self, day: u32) -> String {
+        self.day(day)
+            .and_then(|d| d.input.clone())
+            .unwrap_or_else(|| format!("{}/day_{}.txt", self.input_dir(), day))
+    }
+
+    /// Every profile with an input under the input root: each directory with
+    /// a `day_N.txt` in it, and None if the root has some of its own. The
+    /// examples in `test` aren't a profile, nor is a name that reads as a day.
+    pub fn profiles(&self) -> Vec<Option<String>> {
+        let has_inputs = |dir: &Path| {
+            fs::read_dir(dir).is_ok_and(|entries| {
+                entries.filter_map(|e| e.ok()).any(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    name.starts_with("day_") && name.ends_with(".txt")
+                })
+            })
+        };
+        let root = Path::new(&self.input_root);
+        let mut profiles = fs::read_dir(root)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|entry| entry.path().is_dir() && has_inputs(&entry.path()))
+                    .map(|entry| entry.file_name().to_string_lossy().to_string())
+                    .filter(|name| name != EXAMPLES_DIR && expect_profile(name).is_ok())
+                    .map(Some)
+                    .collect::<Vec<Option<String>>>()
+            })
+            .unwrap_or_default();
+        profiles.sort();
+        if has_inputs(root) {
+            profiles.insert(0, None);
+        }
+        profiles
+    }
+
+    pub fn read_input(&self, day: u32) -> Result<String, String> {
+        let path = self.input_path(day);
+        let input =
+            fs::read_to_string(&path).map_err(|_| format!("could not open input file {}", path))?;
+        Ok(input.trim_end_matches('\n').to_string())
+    }
+
+    pub fn iterations(&self, day: u32) -> u32 {
+        self.day(day)
+            .and_then(|d| d.iterations)
+            .unwrap_or(self.iterations)
+    }
+
+    /// how long `all` lets each of a day's parts run
+    pub fn timeout(&self, day: u32) -> Option<Duration> {
+        let seconds = self.day(day).and_then(|d| d.timeout).or(self.timeout);
+        seconds.map(|s| Duration::from_secs(s as u64))
+    }
+
+    /// the MiB of heap `all` lets each of a day's parts use
+    pub fn memory_limit(&self, day: u32) -> Option<u32> {
+        self.day(day)
+            .and_then(|d| d.memory_limit)
+            .or(self.memory_limit)
+    }
+
+    /// whether `all` should leave a day's part out
+    /// the bags day 2 plays unless others are given on the command line
+    pub fn bags(&self) -> &[String] {
+        self.day(2).map_or(&[], |d| &d.bags)
+    }
+
+    pub fn skips(&self, day: u32, part: char) -> bool {
+        self.day(day).is_some_and(|d| d.skip.contains(&part))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+year = 2022
+session = "file:~/.aoc-session"
+iterations = 5
+timeout = 30
+ledger = "known/answers.toml"
+
+[days.5]
+skip = ["b"]
+iterations = 1
+timeout = 120
+memory_limit = 512
+
+[days.2]
+bags = ["12 red, 13 green, 14 blue", "20 red"]
+
+[days.8]
+input = "big/day_8.txt"
+"#;
+
+    #[test]
+    fn test_config_from_str() {
+        let config = Config::from_str(CONFIG).unwrap();
+        assert_eq!(config.year, 2022);
+        assert_eq!(
+            config.session,
+            SessionSource::File("~/.aoc-session".to_string())
+        );
+        assert_eq!(config.output, "text");
+        assert_eq!(config.ledger, "known/answers.toml");
+        assert!(config.skips(5, 'b'));
+        assert!(!config.skips(5, 'a'));
+        assert_eq!(config.iterations(5), 1);
+        assert_eq!(config.iterations(6), 5);
+        assert_eq!(config.timeout(5), Some(Duration::from_secs(120)));
+        assert_eq!(config.timeout(6), Some(Duration::from_secs(30)));
+        assert_eq!(config.memory_limit(5), Some(512));
+        assert_eq!(config.memory_limit(6), None);
+        assert_eq!(config.input_path(8), "big/day_8.txt");
+        assert_eq!(config.input_path(9), "inputs/day_9.txt");
+        assert_eq!(config.bags(), ["12 red, 13 green, 14 blue", "20 red"]);
+
+        // printing the settings gives back the same settings
+        assert_eq!(Config::from_str(&config.to_string()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut config = Config::from_str(CONFIG).unwrap();
+        config
+            .apply(&Overrides {
+                input_root: Some("other".to_string()),
+                iterations: Some(3),
+                timeout: Some(10),
+                ..Overrides::default()
+            })
+            .unwrap();
+        assert_eq!(config.year, 2022);
+        assert_eq!(config.input_path(8), "other/day_8.txt");
+        assert_eq!(config.iterations(5), 3);
+        assert_eq!(config.timeout(5), Some(Duration::from_secs(10)));
+        assert_eq!(config.memory_limit(5), Some(512));
+        assert!(config.skips(5, 'b'));
+
+        config
+            .apply(&Overrides {
+                profile: Some("alice".to_string()),
+                ..Overrides::default()
+            })
+            .unwrap();
+        assert_eq!(config.input_path(8), "other/alice/day_8.txt");
+
+        let bad_session = Overrides {
+            session: Some("keyring".to_string()),
+            ..Overrides::default()
+        };
+        assert!(config.apply(&bad_session).is_err());
+        let day_like_profile = Overrides {
+            profile: Some("2023".to_string()),
+            ..Overrides::default()
+        };
+        assert!(config.apply(&day_like_profile).is_err());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let error = |s: &str| Config::from_str(s).unwrap_err();
+        assert_eq!(error("yaer = 2023"), "unknown setting yaer");
+        assert_eq!(
+            error("output = \"xml\""),
+            "output must be text or json, found [xml]"
+        );
+        assert_eq!(
+            error("[days.26]\nskip = [\"a\"]"),
+            "days.26 is not a day between 1 and 25"
+        );
+        assert_eq!(
+            error("[days.5]\nskip = [\"c\"]"),
+            "days.5.skip has unknown part [c]"
+        );
+        assert_eq!(
+            error("[days.3]\nbags = [\"1 red\"]"),
+            "days.3.bags only applies to day 2"
+        );
+        assert_eq!(
+            error("timeout = 0"),
+            "timeout must be a positive number, found 0"
+        );
+        assert_eq!(
+            error("iterations = 0"),
+            "iterations must be a positive number, found 0"
+        );
+        assert_eq!(
+            error("[days.5]\nmemory_limit = 0"),
+            "days.5.memory_limit must be a positive number, found 0"
+        );
+        assert_eq!(
+            error("profile = \"1st\""),
+            "profile [1st] can't start with a digit, as days do"
+        );
+    }
+
+    #[test]
+    fn test_profiles() {
+        let root = env::temp_dir().join(format!("aoc-profiles-{}", std::process::id()));
+        for dir in ["alice", "bob", "test", "2nd", "empty"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            if dir != "empty" {
+                fs::write(root.join(dir).join("day_1.txt"), "").unwrap();
+            }
+        }
+        let config = Config {
+            input_root: root.to_string_lossy().to_string(),
+            ..Config::default()
+        };
+        let profiles = config.profiles();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            profiles,
+            vec![Some("alice".to_string()), Some("bob".to_string())]
+        );
+    }
+}
@@ -1,6 +1,17 @@
-use advent_of_code_2023::to_u32;
+use crate::{checked, fold_lines, trace_event};
+use aho_corasick::{AhoCorasick, Match};
+use std::cmp::Reverse;
+use std::io::BufRead;
+use std::str::FromStr;
 
-const DIGIT_MAPPINGS: [(&str, u32); 9] = [
+const SUM_CALIBRATION_VALUES: &str = "summing calibration values";
+
+pub struct CalibrationDocument<'a> {
+    pub lines: Vec<&'a str>,
+}
+
+/// the words part b reads as digits unless it's given another vocabulary
+pub const ENGLISH_DIGIT_WORDS: [(&str, u32); 9] = [
     ("one", 1),
     ("two", 2),
     ("three", 3),
@@ -12,77 +23,177 @@ const DIGIT_MAPPINGS: [(&str, u32); 9] = [
     ("nine", 9),
 ];
 
+fn digit_value(b: u8) -> u32 {
+    (b - b'0') as u32
+}
+
 fn find_first_digit(input: &str) -> u32 {
-    input.chars().find_map(|c| c.to_digit(10)).unwrap()
+    digit_value(input.bytes().find(|b| b.is_ascii_digit()).unwrap())
+}
+
+fn find_last_digit(input: &str) -> u32 {
+    digit_value(input.bytes().rev().find(|b| b.is_ascii_digit()).unwrap())
 }
 
 fn calc_calibration_value_part_a(line: &str) -> u32 {
-    let first_digit = find_first_digit(line);
-    let last_digit = find_first_digit(&line.chars().rev().collect::<String>());
-    let formatted_str = format!("{}{}", first_digit.to_string(), last_digit.to_string());
-    to_u32(&formatted_str)
+    let value = find_first_digit(line) * 10 + find_last_digit(line);
+    trace_event!("calibration", line = line, value = value);
+    value
+}
+
+/// Finds the digits 0-9, and any words standing in for numbers, in a line of
+/// the document. Every match is found in a single pass over the line,
+/// including overlapping ones like the `eight` and `two` in "eightwo".
+pub struct DigitMatcher {
+    automaton: AhoCorasick,
+    /// the value of each pattern, indexed by pattern id
+    values: Vec<u32>,
 }
 
-fn matches_digit(current_char: char, idx: usize, input: &str) -> Option<u32> {
-    if current_char.is_digit(10) {
-        return current_char.to_digit(10);
+impl DigitMatcher {
+    /// builds a matcher for the digits plus `words`, whose values may have
+    /// more than one digit (e.g. `("twelve", 12)`)
+    pub fn new<S: AsRef<str>>(words: &[(S, u32)]) -> DigitMatcher {
+        let (patterns, values): (Vec<String>, Vec<u32>) = (0..10)
+            .map(|digit| (digit.to_string(), digit))
+            .chain(
+                words
+                    .iter()
+                    .map(|(word, value)| (word.as_ref().to_string(), *value)),
+            )
+            .unzip();
+        let automaton = AhoCorasick::new(&patterns)
+            .unwrap_or_else(|e| panic!("Unable to build a digit matcher: {}", e));
+        DigitMatcher { automaton, values }
+    }
+
+    pub fn english() -> DigitMatcher {
+        DigitMatcher::new(&ENGLISH_DIGIT_WORDS)
     }
 
-    for &(word, digit) in DIGIT_MAPPINGS.iter() {
-        if input[idx..].starts_with(word) {
-            return Some(digit);
+    /// The values of the first and last number in `line`. Where two words
+    /// start at the same place the longer one wins, so `seventeen` beats `seven`.
+    pub fn first_and_last(&self, line: &str) -> Option<(u32, u32)> {
+        let mut first: Option<Match> = None;
+        let mut last: Option<Match> = None;
+        for found in self.automaton.find_overlapping_iter(line) {
+            let is_earlier =
+                |m: Match| (found.start(), Reverse(found.len())) < (m.start(), Reverse(m.len()));
+            if first.is_none_or(is_earlier) {
+                first = Some(found);
+            }
+            let is_later = |m: Match| (found.start(), found.len()) > (m.start(), m.len());
+            if last.is_none_or(is_later) {
+                last = Some(found);
+            }
         }
+        let value = |m: Match| self.values[m.pattern().as_usize()];
+        Some((value(first?), value(last?)))
     }
-    None
 }
 
-fn find_first_digit_incl_text_repr(input: &str) -> u32 {
-    input
-        .chars()
-        .enumerate()
-        .find_map(|(idx, c)| matches_digit(c, idx, input))
-        .unwrap()
+/// Reads a vocabulary with one `word=value` pair per line, e.g. `zwei=2`.
+/// Blank lines and lines starting with `#` are skipped.
+impl FromStr for DigitMatcher {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = s
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (word, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected word=value, found \"{}\"", line))?;
+                if word.trim().is_empty() {
+                    return Err(format!("no word before the value in \"{}\"", line));
+                }
+                let value = value
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| format!("\"{}\" is not a number", value.trim()))?;
+                Ok((word.trim(), value))
+            })
+            .collect::<Result<Vec<(&str, u32)>, String>>()?;
+        Ok(DigitMatcher::new(&words))
+    }
 }
 
-fn find_last_digit_incl_text_repr(input: &str) -> u32 {
-    for n in (0..input.len()).rev() {
-        let c = input.chars().nth(n).unwrap();
-        if let Some(digit) = matches_digit(c, n, &input) {
-            return digit;
-        }
+fn leading_digit(mut value: u32) -> u32 {
+    while value >= 10 {
+        value /= 10;
     }
-    panic!("The input {} should always contain a digit", input);
+    value
+}
+
+/// combines the first digit of the first number with the last digit of the
+/// last number, so a multi-digit word still gives a two digit value
+fn calc_calibration_value_part_b(line: &str, matcher: &DigitMatcher) -> u32 {
+    let (first, last) = matcher
+        .first_and_last(line)
+        .unwrap_or_else(|| panic!("The input {} should always contain a digit", line));
+    let value = leading_digit(first) * 10 + last % 10;
+    trace_event!("calibration", line = line, value = value);
+    value
 }
 
-fn calc_calibration_value_part_b(line: &str) -> u32 {
-    let first_digit = find_first_digit_incl_text_repr(line);
-    let last_digit = find_last_digit_incl_text_repr(line);
-    let formatted_str = format!("{}{}", first_digit.to_string(), last_digit.to_string());
-    to_u32(&formatted_str)
+pub fn parse(input: &str) -> CalibrationDocument<'_> {
+    let lines = input.split("\n").collect::<Vec<&str>>();
+    CalibrationDocument { lines }
 }
 
-pub fn part_a(input: &str) -> u32 {
-    let calibration_lines = input.split("\n").collect::<Vec<&str>>();
-    let calibration_values = calibration_lines
+pub fn solve_part_a(document: &CalibrationDocument) -> u64 {
+    let values = document
+        .lines
         .iter()
-        .map(|x| calc_calibration_value_part_a(*x))
-        .collect::<Vec<u32>>();
-    calibration_values.iter().sum::<u32>()
+        .map(|x| calc_calibration_value_part_a(x) as u64);
+    checked::sum(values, 1, SUM_CALIBRATION_VALUES)
 }
 
-pub fn part_b(input: &str) -> u32 {
-    let calibration_lines = input.split("\n").collect::<Vec<&str>>();
-    let calibration_values = calibration_lines
+pub fn solve_part_b(document: &CalibrationDocument) -> u64 {
+    solve_part_b_with(document, &DigitMatcher::english())
+}
+
+/// part b reading numbers with the given matcher instead of the English words
+pub fn solve_part_b_with(document: &CalibrationDocument, matcher: &DigitMatcher) -> u64 {
+    let values = document
+        .lines
         .iter()
-        .map(|x| calc_calibration_value_part_b(&x))
-        .collect::<Vec<u32>>();
-    calibration_values.iter().sum::<u32>()
+        .map(|x| calc_calibration_value_part_b(x, matcher) as u64);
+    checked::sum(values, 1, SUM_CALIBRATION_VALUES)
+}
+
+pub fn stream_part_a(reader: impl BufRead) -> u64 {
+    fold_lines(reader, 0u64, |sum, line| {
+        let value = calc_calibration_value_part_a(line) as u64;
+        checked::expect(sum.checked_add(value), 1, SUM_CALIBRATION_VALUES)
+    })
+}
+
+pub fn stream_part_b(reader: impl BufRead) -> u64 {
+    stream_part_b_with(reader, &DigitMatcher::english())
+}
+
+pub fn stream_part_b_with(reader: impl BufRead, matcher: &DigitMatcher) -> u64 {
+    fold_lines(reader, 0u64, |sum, line| {
+        let value = calc_calibration_value_part_b(line, matcher) as u64;
+        checked::expect(sum.checked_add(value), 1, SUM_CALIBRATION_VALUES)
+    })
+}
+
+pub fn part_a(input: &str) -> u64 {
+    solve_part_a(&parse(input))
+}
+
+pub fn part_b(input: &str) -> u64 {
+    solve_part_b(&parse(input))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_test_file;
+    use crate::read_test_file;
 
     #[test]
     fn test_find_first_digit() {
@@ -91,15 +202,26 @@ mod tests {
     }
 
     #[test]
-    fn test_find_first_digit_incl_text_repr() {
-        let input = "five2threefour";
-        assert_eq!(find_first_digit_incl_text_repr(input), 5);
+    fn test_first_and_last() {
+        let matcher = DigitMatcher::english();
+        assert_eq!(matcher.first_and_last("five2threefour"), Some((5, 4)));
+        assert_eq!(matcher.first_and_last("twosix8nine"), Some((2, 9)));
+        assert_eq!(matcher.first_and_last("eightwo"), Some((8, 2)));
+        assert_eq!(matcher.first_and_last("ñañaseven→ünf"), Some((7, 7)));
+        assert_eq!(matcher.first_and_last("abc"), None);
     }
 
     #[test]
-    fn test_find_last_digit_incl_text_repr() {
-        let input = "twosix8nine";
-        assert_eq!(find_last_digit_incl_text_repr(input), 9);
+    fn test_custom_vocabulary() {
+        let matcher =
+            DigitMatcher::from_str("# German\nzwei=2\nsieben = 7\nsiebzehn=17\nnull=0").unwrap();
+        assert_eq!(matcher.first_and_last("xsiebzehnzweiü"), Some((17, 2)));
+        assert_eq!(calc_calibration_value_part_b("siebzehn3null", &matcher), 10);
+        assert!(DigitMatcher::from_str("zwei").is_err());
+        assert!(DigitMatcher::from_str("zwei=2\n=5").is_err());
+
+        let overlapping = DigitMatcher::new(&[("seven", 7), ("seventeen", 17)]);
+        assert_eq!(overlapping.first_and_last("seventeen"), Some((17, 17)));
     }
 
     #[test]
@@ -113,4 +235,11 @@ mod tests {
         let input = read_test_file(1);
         assert_eq!(part_b(&input), 159);
     }
+
+    #[test]
+    fn test_stream() {
+        let input = read_test_file(1);
+        assert_eq!(stream_part_a(input.as_bytes()), 165);
+        assert_eq!(stream_part_b(input.as_bytes()), 159);
+    }
 }
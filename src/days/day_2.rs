@@ -1,92 +1,255 @@
-use advent_of_code_2023::to_u32;
-
-struct GameDraw {
-    red: u32,
-    blue: u32,
-    green: u32,
-}
-
-struct Game {
-    id: u32,
-    draws: Vec<GameDraw>,
-}
-
-fn parse_game_draw(draw_str: &str) -> GameDraw {
-    let (mut red, mut green, mut blue) = (0u32, 0u32, 0u32);
-    let draw_str_elems = draw_str.split(",").map(|x| x.trim()).collect::<Vec<&str>>();
-
-    for elem in draw_str_elems {
-        if elem.ends_with("red") {
-            red = to_u32(&elem.replace("red", "").trim())
-        } else if elem.ends_with("green") {
-            green = to_u32(&elem.replace("green", "").trim())
-        } else if elem.ends_with("blue") {
-            blue = to_u32(&elem.replace("blue", "").trim())
-        } else {
-            panic!(
-                "Unrecognised element {} when parsing game draw {}",
-                elem, draw_str
-            );
+use crate::{checked, fold_lines, parallel, to_u32, trace_event};
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::BufRead;
+
+const MULTIPLY_FEWEST_CUBES: &str = "multiplying the fewest cubes of each colour";
+const SUM_IDS: &str = "summing the ids of possible games";
+const SUM_POWERS: &str = "summing game powers";
+
+/// the cubes shown in one draw, counted by colour
+pub struct GameDraw<'a> {
+    pub cubes: BTreeMap<&'a str, u32>,
+}
+
+pub struct Game<'a> {
+    pub id: u32,
+    pub draws: Vec<GameDraw<'a>>,
+}
+
+pub struct GameRecord<'a> {
+    pub games: Vec<Game<'a>>,
+}
+
+/// How many cubes of each colour are in the bag. Any colour the bag doesn't
+/// list counts as zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bag<'a> {
+    pub limits: BTreeMap<&'a str, u32>,
+}
+
+/// example: "3 blue, 4 red"
+fn parse_cubes(cubes_str: &str) -> Result<BTreeMap<&str, u32>, String> {
+    let mut cubes = BTreeMap::new();
+
+    for elem in cubes_str.split(",").map(|x| x.trim()) {
+        let count = elem
+            .split_once(" ")
+            .filter(|(_, colour)| !colour.trim().is_empty())
+            .and_then(|(count, colour)| Some((count.parse::<u32>().ok()?, colour.trim())));
+        match count {
+            Some((count, colour)) => {
+                cubes.insert(colour, count);
+            }
+            None => {
+                return Err(format!(
+                    "Unrecognised element {} when parsing cubes {}",
+                    elem, cubes_str
+                ))
+            }
+        }
+    }
+    Ok(cubes)
+}
+
+impl<'a> Bag<'a> {
+    /// reads a bag like `From`, returning an error rather than panicking
+    pub fn parse(s: &'a str) -> Result<Bag<'a>, String> {
+        Ok(Bag {
+            limits: parse_cubes(s)?,
+        })
+    }
+
+    pub fn count(&self, colour: &str) -> u32 {
+        self.limits.get(colour).copied().unwrap_or(0)
+    }
+
+    /// whether every draw of the game fits in the bag
+    pub fn can_play(&self, game: &Game) -> bool {
+        game.draws.iter().all(|draw| {
+            draw.cubes
+                .iter()
+                .all(|(colour, &count)| count <= self.count(colour))
+        })
+    }
+
+    /// the product of the counts of `colours`, where a missing colour counts as zero
+    pub fn power<'c>(&self, colours: impl Iterator<Item = &'c str>) -> u64 {
+        let counts = colours.map(|colour| self.count(colour) as u64);
+        checked::product(counts, 2, MULTIPLY_FEWEST_CUBES)
+    }
+}
+
+/// the bag from the puzzle, 12 red, 13 green and 14 blue cubes
+impl Default for Bag<'static> {
+    fn default() -> Self {
+        Bag {
+            limits: BTreeMap::from([("red", 12), ("green", 13), ("blue", 14)]),
         }
     }
+}
+
+/// reads a bag written like a draw, e.g. "12 red, 13 green, 14 blue"
+impl<'a> From<&'a str> for Bag<'a> {
+    fn from(s: &'a str) -> Self {
+        Bag::parse(s).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
 
-    GameDraw { red, green, blue }
+impl fmt::Display for Bag<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cubes = self
+            .limits
+            .iter()
+            .map(|(colour, count)| format!("{} {}", count, colour))
+            .join(", ");
+        write!(f, "{}", cubes)
+    }
 }
 
-fn game_draw_is_valid(draw: &GameDraw) -> bool {
-    draw.red <= 12 && draw.green <= 13 && draw.blue <= 14
+impl<'a> Game<'a> {
+    /// the smallest bag the game could have been played with
+    pub fn minimum_bag(&self) -> Bag<'a> {
+        let mut limits = BTreeMap::new();
+        for draw in &self.draws {
+            for (&colour, &count) in &draw.cubes {
+                let limit = limits.entry(colour).or_insert(0);
+                *limit = u32::max(*limit, count);
+            }
+        }
+        Bag { limits }
+    }
 }
 
-fn build_game(game_str: &str) -> Game {
+fn build_game(game_str: &str) -> Game<'_> {
     let (id_str, result_str) = game_str.split_once(":").unwrap();
-    let id = to_u32(&id_str.replace("Game ", ""));
+    let id = to_u32(id_str.trim_start_matches("Game "));
 
     let draws = result_str
         .split(";")
-        .map(|x| parse_game_draw(x))
+        .map(|draw_str| GameDraw {
+            cubes: parse_cubes(draw_str).unwrap_or_else(|e| panic!("{}", e)),
+        })
         .collect::<Vec<GameDraw>>();
 
     Game { id, draws }
 }
 
-/// returns the Some(game_id) if the game is valid. None if invalid
-fn game_is_valid(game_str: &str) -> Option<u32> {
-    let game = build_game(game_str);
+pub fn parse(input: &str) -> GameRecord<'_> {
+    let games = input.split("\n").map(build_game).collect::<Vec<Game>>();
+    GameRecord { games }
+}
 
-    let valid_games = game
-        .draws
+/// the ids of every game `bag` could have played
+pub fn playable_games(record: &GameRecord, bag: &Bag) -> Vec<u32> {
+    record
+        .games
         .iter()
-        .fold(0, |vg, draw| vg + game_draw_is_valid(draw) as usize);
+        .filter(|game| bag.can_play(game))
+        .map(|game| game.id)
+        .collect_vec()
+}
+
+/// `distribution[k]` is how many games exactly `k` of the `bags` could have played
+pub fn possibility_distribution(record: &GameRecord, bags: &[Bag]) -> Vec<usize> {
+    let mut distribution = vec![0; bags.len() + 1];
+    for game in &record.games {
+        let playable_with = bags.iter().filter(|bag| bag.can_play(game)).count();
+        distribution[playable_with] += 1;
+    }
+    distribution
+}
 
-    if game.draws.len() == valid_games {
-        Some(game.id)
+/// the id a game adds to the part a sum, 0 when the game is impossible
+fn counted_game_id(game: &Game, possible: bool) -> u64 {
+    trace_event!("game", id = game.id, possible = possible);
+    if possible {
+        game.id as u64
     } else {
-        None
+        0
     }
 }
 
-fn calc_game_power(game_str: &str) -> u32 {
-    let game = build_game(game_str);
-    let max_red = game.draws.iter().max_by_key(|d| d.red).unwrap().red;
-    let max_green = game.draws.iter().max_by_key(|d| d.green).unwrap().green;
-    let max_blue = game.draws.iter().max_by_key(|d| d.blue).unwrap().blue;
-    max_red * max_green * max_blue
+/// Part b's power of a game: its minimum bag's counts of each of the bag's
+/// colours multiplied together. A colour the game never shows counts as zero.
+fn game_power(game: &Game, minimum_bag: &Bag, bag: &Bag) -> u64 {
+    let power = minimum_bag.power(bag.limits.keys().copied());
+    trace_event!(
+        "game_power",
+        id = game.id,
+        minimum_bag = minimum_bag.to_string(),
+        power = power
+    );
+    power
 }
 
-pub fn part_a(input: &str) -> u32 {
-    let games = input.split("\n").collect::<Vec<&str>>();
-    games.iter().map(|x| game_is_valid(x)).flatten().sum()
+pub fn solve_part_a(record: &GameRecord) -> u64 {
+    solve_part_a_with(record, &Bag::default())
 }
 
-pub fn part_b(input: &str) -> u32 {
-    let games = input.split("\n").collect::<Vec<&str>>();
-    games.iter().map(|x| calc_game_power(x)).sum()
+/// part a with a bag other than the puzzle's
+pub fn solve_part_a_with(record: &GameRecord, bag: &Bag) -> u64 {
+    let possible = parallel::map(&record.games, |game| bag.can_play(game));
+    let counted_ids = record
+        .games
+        .iter()
+        .zip(possible)
+        .map(|(game, possible)| counted_game_id(game, possible));
+    checked::sum(counted_ids, 2, SUM_IDS)
+}
+
+pub fn solve_part_b(record: &GameRecord) -> u64 {
+    solve_part_b_with(record, &Bag::default())
+}
+
+/// part b taking its colours from a bag other than the puzzle's
+pub fn solve_part_b_with(record: &GameRecord, bag: &Bag) -> u64 {
+    let minimum_bags = parallel::map(&record.games, Game::minimum_bag);
+    let powers = record
+        .games
+        .iter()
+        .zip(minimum_bags.iter())
+        .map(|(game, minimum_bag)| game_power(game, minimum_bag, bag));
+    checked::sum(powers, 2, SUM_POWERS)
+}
+
+pub fn stream_part_a(reader: impl BufRead) -> u64 {
+    stream_part_a_with(reader, &Bag::default())
+}
+
+pub fn stream_part_a_with(reader: impl BufRead, bag: &Bag) -> u64 {
+    fold_lines(reader, 0u64, |sum, line| {
+        let game = build_game(line);
+        let id = counted_game_id(&game, bag.can_play(&game));
+        checked::expect(sum.checked_add(id), 2, SUM_IDS)
+    })
+}
+
+pub fn stream_part_b(reader: impl BufRead) -> u64 {
+    stream_part_b_with(reader, &Bag::default())
+}
+
+pub fn stream_part_b_with(reader: impl BufRead, bag: &Bag) -> u64 {
+    fold_lines(reader, 0u64, |sum, line| {
+        let game = build_game(line);
+        let power = game_power(&game, &game.minimum_bag(), bag);
+        checked::expect(sum.checked_add(power), 2, SUM_POWERS)
+    })
+}
+
+pub fn part_a(input: &str) -> u64 {
+    solve_part_a(&parse(input))
+}
+
+pub fn part_b(input: &str) -> u64 {
+    solve_part_b(&parse(input))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_test_file;
+    use crate::read_test_file;
 
     #[test]
     fn test_part_a() {
@@ -99,4 +262,43 @@ mod tests {
         let input = read_test_file(2);
         assert_eq!(part_b(&input), 2286);
     }
+
+    #[test]
+    fn test_stream() {
+        let input = read_test_file(2);
+        assert_eq!(stream_part_a(input.as_bytes()), 8);
+        assert_eq!(stream_part_b(input.as_bytes()), 2286);
+    }
+
+    #[test]
+    fn test_custom_bag() {
+        let input = read_test_file(2);
+        let record = parse(&input);
+        let bag = Bag::from("20 red, 20 green, 20 blue, 1 purple");
+        assert_eq!(playable_games(&record, &bag), vec![1, 2, 3, 4, 5]);
+        assert_eq!(solve_part_a_with(&record, &bag), 15);
+        // no game shows purple, so every power is zero
+        assert_eq!(solve_part_b_with(&record, &bag), 0);
+
+        let record = parse("Game 1: 3 purple, 1 red; 2 purple");
+        assert_eq!(solve_part_a(&record), 0);
+        assert_eq!(solve_part_a_with(&record, &Bag::from("3 purple, 1 red")), 1);
+    }
+
+    #[test]
+    fn test_minimum_bag() {
+        let input = read_test_file(2);
+        let record = parse(&input);
+        let minimum_bag = record.games[0].minimum_bag();
+        assert_eq!(minimum_bag, Bag::from("4 red, 2 green, 6 blue"));
+        assert_eq!(minimum_bag.to_string(), "6 blue, 2 green, 4 red");
+    }
+
+    #[test]
+    fn test_possibility_distribution() {
+        let input = read_test_file(2);
+        let record = parse(&input);
+        let bags = [Bag::default(), Bag::from("20 red, 20 green, 20 blue")];
+        assert_eq!(possibility_distribution(&record, &bags), vec![0, 2, 3]);
+    }
 }
@@ -1,170 +1,322 @@
-use advent_of_code_2023::to_u32;
-use itertools::{iproduct, Itertools};
+use crate::{checked, trace_event};
+use itertools::Itertools;
 use std::cmp;
 
-fn get_search_coords(x: usize, y: usize, grid: &Vec<Vec<char>>) -> Vec<(usize, usize)> {
-    let min_x = cmp::max(0, (x as i32) - 1) as usize;
-    let max_x = cmp::min(x + 1, grid[y].len() - 1);
-    let min_y = cmp::max(0, (y as i32) - 1) as usize;
-    let max_y = cmp::min(y + 1, grid.len() - 1);
+const READ_NUMBER: &str = "reading a number";
+const MULTIPLY_GEAR_NUMBERS: &str = "multiplying gear numbers";
+const SUM_PART_NUMBERS: &str = "summing part numbers";
+const SUM_GEAR_RATIOS: &str = "summing gear ratios";
 
-    let x_range = min_x..max_x + 1;
-    let y_range = min_y..max_y + 1;
+/// a number's index into the schematic's numbers, in reading order
+pub type NumberId = usize;
+/// a symbol's index into the schematic's symbols, in reading order
+pub type SymbolId = usize;
 
-    iproduct!(x_range, y_range)
-        .filter(|coords| *coords != (x, y))
-        .collect::<Vec<(usize, usize)>>()
+/// a number in the schematic, spanning columns `start_x..=end_x` of row `y`
+pub struct GridNumber {
+    pub value: u32,
+    pub y: usize,
+    pub start_x: usize,
+    pub end_x: usize,
 }
 
-fn check_validity_around_point(x: usize, y: usize, grid: &Vec<Vec<char>>) -> bool {
-    for (x_coord, y_coord) in get_search_coords(x, y, grid) {
-        let cell = grid[y_coord][x_coord];
-        let is_symbol = cell != '.' && !cell.is_ascii_alphanumeric();
-        if is_symbol {
-            return true;
-        }
-    }
-    false
+/// any cell that isn't a dot, a digit or a letter
+pub struct Symbol {
+    pub symbol: u8,
+    pub x: usize,
+    pub y: usize,
 }
 
-fn find_multipliers(grid: &Vec<Vec<char>>) -> Vec<(usize, usize)> {
-    let mut multiplier_idxs = Vec::<(usize, usize)>::new();
+/// A symbol touching the number of numbers asked for, e.g. a `*` next to
+/// exactly two numbers in part b.
+pub struct Gear {
+    pub x: usize,
+    pub y: usize,
+    pub numbers: Vec<u32>,
+}
 
-    for y in 0..grid.len() {
-        for x in 0..grid[y].len() {
-            if grid[y][x] == '*' {
-                multiplier_idxs.push((x, y));
-            }
-        }
+impl Gear {
+    pub fn ratio(&self) -> u64 {
+        let numbers = self.numbers.iter().map(|&number| number as u64);
+        checked::product(numbers, 3, MULTIPLY_GEAR_NUMBERS)
     }
-    multiplier_idxs
 }
 
-fn find_full_number(x: usize, y: usize, grid: &Vec<Vec<char>>) -> u32 {
-    let ref row = grid[y];
-    let mut start_idx = x;
-    let mut end_idx = x;
+/// Every number and symbol in the engine schematic, with an index of which
+/// numbers and symbols touch each other (diagonals included). Each side of
+/// the index is one flat list grouped by id, with `offsets[id]..offsets[id + 1]`
+/// marking out an id's neighbours.
+pub struct Schematic<'a> {
+    pub grid: Vec<&'a [u8]>,
+    pub numbers: Vec<GridNumber>,
+    pub symbols: Vec<Symbol>,
+    number_offsets: Vec<usize>,
+    number_symbols: Vec<SymbolId>,
+    symbol_offsets: Vec<usize>,
+    symbol_numbers: Vec<NumberId>,
+}
 
-    while start_idx >= 1 && row[start_idx - 1].is_digit(10) {
-        start_idx -= 1;
+/// groups `(from, to)` pairs by `from` into offsets and a flat list of `to`s
+fn group_by_id(
+    pairs: impl Iterator<Item = (usize, usize)> + Clone,
+    count: usize,
+) -> (Vec<usize>, Vec<usize>) {
+    let mut offsets = vec![0; count + 1];
+    for (from, _) in pairs.clone() {
+        offsets[from + 1] += 1;
+    }
+    for id in 0..count {
+        offsets[id + 1] += offsets[id];
     }
-    while end_idx < row.len() - 1 && row[end_idx + 1].is_digit(10) {
-        end_idx += 1;
+    let mut next = offsets.clone();
+    let mut grouped = vec![0; offsets[count]];
+    for (from, to) in pairs {
+        grouped[next[from]] = to;
+        next[from] += 1;
     }
-    let number_str = &row[start_idx..end_idx + 1].iter().collect::<String>();
-    to_u32(number_str)
+    (offsets, grouped)
 }
 
-fn calculate_multiplier(x: usize, y: usize, grid: &Vec<Vec<char>>) -> Option<u32> {
-    let search_coords = get_search_coords(x, y, grid);
-    let neighbouring_digit_coords = search_coords
-        .iter()
-        .filter(|(x_c, y_c)| grid[*y_c][*x_c].is_digit(10))
-        .collect::<Vec<&(usize, usize)>>();
+impl<'a> Schematic<'a> {
+    pub fn new(grid: Vec<&'a [u8]>) -> Schematic<'a> {
+        let numbers = find_numbers(&grid);
+        let symbols = find_symbols(&grid);
+        let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut symbol_ids = vec![None; width * grid.len()];
+        for (id, symbol) in symbols.iter().enumerate() {
+            symbol_ids[symbol.y * width + symbol.x] = Some(id);
+        }
+
+        let mut touching = Vec::<(NumberId, SymbolId)>::new();
+        for (number_id, number) in numbers.iter().enumerate() {
+            let xs = number.start_x.saturating_sub(1)..=cmp::min(number.end_x + 1, width - 1);
+            let ys = number.y.saturating_sub(1)..=cmp::min(number.y + 1, grid.len() - 1);
+            for (y, x) in ys.cartesian_product(xs) {
+                if let Some(symbol_id) = symbol_ids[y * width + x] {
+                    touching.push((number_id, symbol_id));
+                }
+            }
+        }
+        let (number_offsets, number_symbols) = group_by_id(touching.iter().copied(), numbers.len());
+        let (symbol_offsets, symbol_numbers) =
+            group_by_id(touching.iter().map(|&(n, s)| (s, n)), symbols.len());
+
+        Schematic {
+            grid,
+            numbers,
+            symbols,
+            number_offsets,
+            number_symbols,
+            symbol_offsets,
+            symbol_numbers,
+        }
+    }
+
+    /// the symbols touching a number
+    pub fn symbols_next_to(&self, number: NumberId) -> &[SymbolId] {
+        &self.number_symbols[self.number_offsets[number]..self.number_offsets[number + 1]]
+    }
 
-    let neighbouring_numbers = neighbouring_digit_coords
-        .iter()
-        .map(|(x_c, y_c)| find_full_number(*x_c, *y_c, grid))
-        .unique()
-        .collect::<Vec<u32>>();
+    /// the numbers touching a symbol, each counted once even if equal in value
+    pub fn numbers_next_to(&self, symbol: SymbolId) -> &[NumberId] {
+        &self.symbol_numbers[self.symbol_offsets[symbol]..self.symbol_offsets[symbol + 1]]
+    }
 
-    if neighbouring_numbers.len() == 2 {
-        return Some(neighbouring_numbers[0] * neighbouring_numbers[1]);
+    /// whether a number touches `symbol`, or any symbol when it's None
+    pub fn is_part_number(&self, number: NumberId, symbol: Option<u8>) -> bool {
+        self.symbols_next_to(number)
+            .iter()
+            .any(|&id| symbol.is_none_or(|s| self.symbols[id].symbol == s))
     }
-    None
+
+    /// every `symbol` touching exactly `neighbours` numbers
+    pub fn gears(&self, symbol: u8, neighbours: usize) -> Vec<Gear> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter(|(id, s)| s.symbol == symbol && self.numbers_next_to(*id).len() == neighbours)
+            .map(|(id, s)| Gear {
+                x: s.x,
+                y: s.y,
+                numbers: self
+                    .numbers_next_to(id)
+                    .iter()
+                    .map(|&number| self.numbers[number].value)
+                    .collect_vec(),
+            })
+            .collect_vec()
+    }
+}
+
+fn is_symbol(cell: u8) -> bool {
+    cell != b'.' && !cell.is_ascii_alphanumeric()
+}
+
+fn digits_to_u32(digits: &[u8]) -> u32 {
+    digits.iter().fold(0, |acc, &digit| {
+        let shifted = checked::mul(acc, 10, 3, READ_NUMBER);
+        checked::add(shifted, (digit - b'0') as u32, 3, READ_NUMBER)
+    })
 }
 
-fn build_grid(input: &str) -> Vec<Vec<char>> {
+pub fn build_grid(input: &str) -> Vec<&[u8]> {
     input
         .split("\n")
-        .map(|x| x.chars().collect::<Vec<char>>())
-        .collect::<Vec<Vec<char>>>()
+        .map(|x| x.as_bytes())
+        .collect::<Vec<&[u8]>>()
 }
 
-pub fn part_a(input: &str) -> u32 {
-    let grid = build_grid(input);
-
-    let mut part_sum = 0u32;
+/// every run of digits in the grid, in reading order
+fn find_numbers(grid: &[&[u8]]) -> Vec<GridNumber> {
+    let mut numbers = Vec::<GridNumber>::new();
 
-    for y in 0..grid.len() {
-        let mut current_number = Vec::<char>::new();
-        let mut current_number_adjacent_to_symbol = false;
+    for (y, row) in grid.iter().enumerate() {
+        let mut x = 0;
+        while x < row.len() {
+            if !row[x].is_ascii_digit() {
+                x += 1;
+                continue;
+            }
+            let start_x = x;
+            while x < row.len() && row[x].is_ascii_digit() {
+                x += 1;
+            }
+            numbers.push(GridNumber {
+                value: digits_to_u32(&row[start_x..x]),
+                y,
+                start_x,
+                end_x: x - 1,
+            });
+        }
+    }
+    numbers
+}
 
-        for x in 0..grid[y].len() {
-            let current_char = grid[y][x];
+/// every symbol in the grid, in reading order
+fn find_symbols(grid: &[&[u8]]) -> Vec<Symbol> {
+    let mut symbols = Vec::<Symbol>::new();
 
-            if current_char.is_digit(10) {
-                current_number.push(current_char);
-                if !current_number_adjacent_to_symbol {
-                    current_number_adjacent_to_symbol = check_validity_around_point(x, y, &grid);
-                }
+    for (y, row) in grid.iter().enumerate() {
+        for (x, &cell) in row.iter().enumerate() {
+            if is_symbol(cell) {
+                symbols.push(Symbol { symbol: cell, x, y });
             }
+        }
+    }
+    symbols
+}
 
-            if !current_char.is_digit(10) || x == grid[y].len() - 1 {
-                if current_number.len() > 0 && current_number_adjacent_to_symbol {
-                    part_sum += to_u32(&current_number.iter().collect::<String>());
-                }
-                // reset ready for next number
-                current_number = Vec::<char>::new();
-                current_number_adjacent_to_symbol = false;
-            }
+pub fn parse(input: &str) -> Schematic<'_> {
+    Schematic::new(build_grid(input))
+}
+
+/// the sum of the numbers touching `symbol`, or touching any symbol when it's None
+pub fn sum_part_numbers(schematic: &Schematic, symbol: Option<u8>) -> u64 {
+    let mut part_sum = 0u64;
+
+    for (id, number) in schematic.numbers.iter().enumerate() {
+        let is_part_number = schematic.is_part_number(id, symbol);
+        trace_event!(
+            "number",
+            number = number.value,
+            line = number.y + 1,
+            column = number.start_x + 1,
+            part_number = is_part_number
+        );
+        if is_part_number {
+            let value = number.value as u64;
+            part_sum = checked::expect(part_sum.checked_add(value), 3, SUM_PART_NUMBERS);
         }
     }
     part_sum
 }
 
-pub fn part_b(input: &str) -> u32 {
-    let grid = build_grid(input);
-    let multipliers = find_multipliers(&grid);
-    let mut part_sum = 0u32;
+/// the sum of the ratios of every `symbol` touching exactly `neighbours` numbers
+pub fn sum_gear_ratios(schematic: &Schematic, symbol: u8, neighbours: usize) -> u64 {
+    let mut part_sum = 0u64;
 
-    for (x, y) in multipliers {
-        if let Some(result) = calculate_multiplier(x, y, &grid) {
-            part_sum += result;
-        }
+    for gear in schematic.gears(symbol, neighbours) {
+        trace_event!(
+            "gear",
+            line = gear.y + 1,
+            column = gear.x + 1,
+            numbers = gear.numbers,
+            ratio = gear.ratio()
+        );
+        part_sum = checked::expect(part_sum.checked_add(gear.ratio()), 3, SUM_GEAR_RATIOS);
     }
     part_sum
 }
 
+pub fn solve_part_a(schematic: &Schematic) -> u64 {
+    sum_part_numbers(schematic, None)
+}
+
+pub fn solve_part_b(schematic: &Schematic) -> u64 {
+    sum_gear_ratios(schematic, b'*', 2)
+}
+
+pub fn part_a(input: &str) -> u64 {
+    solve_part_a(&parse(input))
+}
+
+pub fn part_b(input: &str) -> u64 {
+    solve_part_b(&parse(input))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_test_file;
+    use crate::read_test_file;
+    use crate::trace::record_events;
 
     #[test]
-    fn test_check_validity_around_point() {
-        let grid = vec![
-            vec!['.', '.', '#'],
-            vec!['.', 'a', '.'],
-            vec!['c', '.', '.'],
-        ];
-        assert_eq!(check_validity_around_point(1, 1, &grid), true);
-        assert_eq!(check_validity_around_point(0, 1, &grid), false);
-        assert_eq!(check_validity_around_point(2, 1, &grid), true);
-        assert_eq!(check_validity_around_point(0, 2, &grid), false);
+    fn test_find_numbers() {
+        let grid: Vec<&[u8]> = vec![b"..123*", b"66..", b".125"];
+        let numbers = find_numbers(&grid)
+            .iter()
+            .map(|n| (n.value, n.y, n.start_x, n.end_x))
+            .collect_vec();
+        assert_eq!(numbers, vec![(123, 0, 2, 4), (66, 1, 0, 1), (125, 2, 1, 3)]);
     }
 
     #[test]
-    fn test_find_multipliers() {
-        let grid = vec![
-            vec!['*', '.', '#'],
-            vec!['.', '*', '.'],
-            vec!['c', '*', '.'],
-        ];
-        let expected = vec![(0, 0), (1, 1), (1, 2)];
-        assert_eq!(find_multipliers(&grid), expected);
+    fn test_adjacency() {
+        let schematic = parse("..#\n.1.\nc..\n2..");
+        assert_eq!(schematic.symbols.len(), 1);
+        assert_eq!(schematic.numbers_next_to(0), &[0]);
+        assert_eq!(schematic.symbols_next_to(0), &[0]);
+        assert!(schematic.symbols_next_to(1).is_empty());
     }
 
     #[test]
-    fn test_find_full_number() {
-        let grid = vec![vec!['.', '.', '1', '2', '3', '*']];
-        assert_eq!(find_full_number(3, 0, &grid), 123);
+    fn test_equal_gear_numbers() {
+        let schematic = parse("12*12");
+        assert_eq!(solve_part_b(&schematic), 144);
+    }
 
-        let grid_ending_in_number = vec![vec!['.', '1', '2', '5']];
-        assert_eq!(find_full_number(1, 0, &grid_ending_in_number), 125);
+    #[test]
+    fn test_symbol_queries() {
+        let input = read_test_file(3);
+        let schematic = parse(&input);
+        assert_eq!(sum_part_numbers(&schematic, Some(b'#')), 633);
+        assert_eq!(sum_gear_ratios(&schematic, b'*', 1), 617);
+        assert_eq!(sum_gear_ratios(&schematic, b'+', 1), 592);
+    }
 
-        let grid_starting_with_number = vec![vec!['6', '6', '.', '.']];
-        assert_eq!(find_full_number(1, 0, &grid_starting_with_number), 66);
+    #[test]
+    fn test_trace_events() {
+        let input = read_test_file(3);
+        let (sum, events) = record_events("number", || part_a(&input));
+        assert_eq!(sum, 4361);
+        // 114 touches no symbol, so isn't a part number
+        for event in [
+            r#"{"event":"number","number":467,"line":1,"column":1,"part_number":true}"#,
+            r#"{"event":"number","number":114,"line":1,"column":6,"part_number":false}"#,
+            r#"{"event":"number","number":58,"line":6,"column":8,"part_number":false}"#,
+        ] {
+            assert!(events.iter().any(|e| e == event), "no {}", event);
+        }
     }
 
     #[test]
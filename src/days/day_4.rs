@@ -1,29 +1,156 @@
-use advent_of_code_2023::to_u32;
-use num::pow;
+use crate::{checked, fold_lines, to_u32, trace_event};
 use std::cmp;
+use std::collections::{HashSet, VecDeque};
+use std::io::BufRead;
 use std::str::FromStr;
 
+const DOUBLE_POINTS: &str = "doubling a card's points";
+const SUM_POINTS: &str = "summing card points";
+const COUNT_COPIES: &str = "counting card copies";
+
 #[derive(Debug, PartialEq)]
-struct Scratchcard {
-    id: u32,
-    winning_numbers: Vec<u32>,
-    drawn_numbers: Vec<u32>,
+pub struct Scratchcard {
+    pub id: u32,
+    pub winning_numbers: HashSet<u32>,
+    pub drawn_numbers: Vec<u32>,
+}
+
+pub struct ScratchcardPile {
+    pub scratchcards: Vec<Scratchcard>,
+}
+
+/// how many points a card is worth for its number of matches
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Scoring {
+    /// one point for the first match, doubled for every match after it
+    #[default]
+    Doubling,
+    /// one point per match
+    Linear,
+    /// the points for 0, 1, 2, ... matches, any more matches scoring the last
+    /// entry and an empty table scoring nothing
+    Table(Vec<u64>),
+}
+
+impl Scoring {
+    pub fn points(&self, matches: usize) -> u64 {
+        match self {
+            Scoring::Doubling if matches == 0 => 0,
+            Scoring::Doubling => {
+                checked::expect(2u64.checked_pow(matches as u32 - 1), 4, DOUBLE_POINTS)
+            }
+            Scoring::Linear => matches as u64,
+            Scoring::Table(points) => points.get(matches).or(points.last()).map_or(0, |&p| p),
+        }
+    }
+}
+
+/// reads "doubling", "linear" or a table like "table:0,1,3,6"
+impl FromStr for Scoring {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(":") {
+            None if s == "doubling" => Ok(Scoring::Doubling),
+            None if s == "linear" => Ok(Scoring::Linear),
+            Some(("table", points)) if points.trim().is_empty() => {
+                Err("the points table is empty".to_string())
+            }
+            Some(("table", points)) => {
+                let points = points
+                    .split(",")
+                    .map(|p| p.trim().parse::<u64>())
+                    .collect::<Result<Vec<u64>, _>>()
+                    .map_err(|_| format!("invalid points table [{}]", points))?;
+                Ok(Scoring::Table(points))
+            }
+            _ => Err(format!(
+                "unrecognised scoring [{}], expected doubling, linear or table:...",
+                s
+            )),
+        }
+    }
+}
+
+/// how the copies won by a card are handed out in part b
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CopyRules {
+    /// most copies any one card can have, the original included
+    pub cap: Option<u64>,
+    /// whether copies won past the last card go to the cards at the start, which
+    /// needs a cap when cards can keep winning each other
+    pub wrap_around: bool,
+}
+
+impl CopyRules {
+    fn capped(&self, copies: u64) -> u64 {
+        self.cap.map_or(copies, |cap| cmp::min(copies, cap))
+    }
 }
 
 impl Scratchcard {
-    fn matching_numbers(&self) -> usize {
+    pub fn matching_numbers(&self) -> usize {
         self.drawn_numbers
             .iter()
-            .filter(|&&d| self.winning_numbers.contains(&&d))
+            .filter(|d| self.winning_numbers.contains(d))
             .count()
     }
 
-    fn winning_points(&self) -> u32 {
-        let matches = self.matching_numbers();
-        if matches == 0 {
-            return 0;
+    pub fn winning_points(&self) -> u64 {
+        Scoring::Doubling.points(self.matching_numbers())
+    }
+}
+
+impl ScratchcardPile {
+    /// Plays out the cascade of won copies, returning how many copies of each
+    /// card end up in the pile. Copies still to be scratched are worked through
+    /// card by card, going round the pile again if copies wrap to earlier cards.
+    pub fn copies(&self, rules: &CopyRules) -> Vec<u64> {
+        let card_count = self.scratchcards.len();
+        let matches = self
+            .scratchcards
+            .iter()
+            .map(|s| s.matching_numbers())
+            .collect::<Vec<usize>>();
+        let mut copies = vec![rules.capped(1); card_count];
+        let mut unscratched = copies.clone();
+        // a cap of 0 leaves no copies to scratch at all
+        let mut remaining = unscratched.iter().filter(|&&c| c > 0).count();
+
+        let mut idx = 0;
+        while remaining > 0 {
+            let scratched = unscratched[idx];
+            if scratched > 0 {
+                unscratched[idx] = 0;
+                remaining -= 1;
+                let won_end = if rules.wrap_around {
+                    idx + matches[idx] + 1
+                } else {
+                    cmp::min(idx + matches[idx] + 1, card_count)
+                };
+                for won_idx in (idx + 1..won_end).map(|i| i % card_count) {
+                    let total = rules.capped(checked::expect(
+                        copies[won_idx].checked_add(scratched),
+                        4,
+                        COUNT_COPIES,
+                    ));
+                    let added = total - copies[won_idx];
+                    if added > 0 {
+                        copies[won_idx] = total;
+                        if unscratched[won_idx] == 0 {
+                            remaining += 1;
+                        }
+                        unscratched[won_idx] = checked::expect(
+                            unscratched[won_idx].checked_add(added),
+                            4,
+                            COUNT_COPIES,
+                        );
+                    }
+                }
+            }
+            idx = (idx + 1) % card_count;
         }
-        pow(2, matches - 1)
+        copies
     }
 }
 
@@ -32,11 +159,11 @@ impl FromStr for Scratchcard {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (id_str, body_str) = s.split_once(":").unwrap();
-        let id = to_u32(&id_str.replace("Card ", "").trim());
+        let id = to_u32(id_str.trim_start_matches("Card"));
         let (winning_str, drawing_str) = body_str.split_once("|").unwrap();
 
-        let winning_numbers = str_to_num_vec(winning_str);
-        let drawn_numbers = str_to_num_vec(drawing_str);
+        let winning_numbers = str_to_nums(winning_str).collect::<HashSet<u32>>();
+        let drawn_numbers = str_to_nums(drawing_str).collect::<Vec<u32>>();
 
         Ok(Scratchcard {
             id,
@@ -46,48 +173,115 @@ impl FromStr for Scratchcard {
     }
 }
 
-fn str_to_num_vec(s: &str) -> Vec<u32> {
-    s.trim()
-        .replace("  ", " ")
-        .split(" ")
-        .map(|x| to_u32(x.trim()))
-        .collect::<Vec<u32>>()
+fn str_to_nums(s: &str) -> impl Iterator<Item = u32> + '_ {
+    s.split_ascii_whitespace().map(to_u32)
 }
 
-pub fn part_a(input: &str) -> u32 {
+pub fn parse(input: &str) -> ScratchcardPile {
     let scratchcards = input
         .split("\n")
         .map(|x| Scratchcard::from_str(x).unwrap())
         .collect::<Vec<Scratchcard>>();
-    scratchcards.iter().map(|s| s.winning_points()).sum()
+    ScratchcardPile { scratchcards }
 }
 
-pub fn part_b(input: &str) -> u32 {
-    let scratchcards = input
-        .split("\n")
-        .map(|x| Scratchcard::from_str(x).unwrap())
-        .collect::<Vec<Scratchcard>>();
-    let scratchcard_matches = scratchcards
-        .iter()
-        .map(|s| s.matching_numbers())
-        .collect::<Vec<usize>>();
-    let mut scratchcard_plays = vec![1u32; scratchcard_matches.len()];
-
-    for (match_idx, matching_numbers) in scratchcard_matches.iter().enumerate() {
-        let range_start_idx = cmp::min(match_idx + 1, scratchcard_plays.len());
-        let range_end_idx = cmp::min(match_idx + matching_numbers + 1, scratchcard_plays.len());
-        let plays = scratchcard_plays[match_idx];
-        for plays_idx in range_start_idx..range_end_idx {
-            scratchcard_plays[plays_idx] += plays;
-        }
+fn card_points(scratchcard: &Scratchcard, scoring: &Scoring) -> u64 {
+    let points = scoring.points(scratchcard.matching_numbers());
+    trace_event!("card", card = scratchcard.id, points = points);
+    points
+}
+
+pub fn solve_part_a(pile: &ScratchcardPile) -> u64 {
+    solve_part_a_with(pile, &Scoring::Doubling)
+}
+
+/// part a scoring cards some other way than doubling
+pub fn solve_part_a_with(pile: &ScratchcardPile, scoring: &Scoring) -> u64 {
+    let points = pile.scratchcards.iter().map(|s| card_points(s, scoring));
+    checked::sum(points, 4, SUM_POINTS)
+}
+
+pub fn solve_part_b(pile: &ScratchcardPile) -> u64 {
+    solve_part_b_with(pile, &CopyRules::default())
+}
+
+/// part b handing out won copies under other rules
+pub fn solve_part_b_with(pile: &ScratchcardPile, rules: &CopyRules) -> u64 {
+    let copies = pile.copies(rules);
+    for (card, &copies) in pile.scratchcards.iter().zip(copies.iter()) {
+        trace_event!(
+            "card_copies",
+            card = card.id,
+            matches = card.matching_numbers(),
+            copies = copies
+        );
+    }
+    checked::sum(copies, 4, COUNT_COPIES)
+}
+
+pub fn stream_part_a(reader: impl BufRead) -> u64 {
+    stream_part_a_with(reader, &Scoring::Doubling)
+}
+
+pub fn stream_part_a_with(reader: impl BufRead, scoring: &Scoring) -> u64 {
+    fold_lines(reader, 0u64, |sum, line| {
+        let points = card_points(&Scratchcard::from_str(line).unwrap(), scoring);
+        checked::expect(sum.checked_add(points), 4, SUM_POINTS)
+    })
+}
+
+pub fn stream_part_b(reader: impl BufRead) -> u64 {
+    stream_part_b_with(reader, &CopyRules::default())
+}
+
+/// Part b without holding the pile: only the copies won for the next few
+/// cards are kept, as a card can win at most one copy per winning number.
+/// Copies can't wrap around, as the first cards are gone by the time the last
+/// ones are read.
+pub fn stream_part_b_with(reader: impl BufRead, rules: &CopyRules) -> u64 {
+    if rules.wrap_around {
+        panic!("Copies that wrap around need the whole pile, so day 4 can't be streamed");
     }
-    scratchcard_plays.iter().sum()
+    // extra copies won for the cards after the current one, nearest first
+    let mut won_copies = VecDeque::<u64>::new();
+    fold_lines(reader, 0, |sum, line| {
+        let card = Scratchcard::from_str(line).unwrap();
+        let matches = card.matching_numbers();
+        let copies = rules.capped(checked::expect(
+            1u64.checked_add(won_copies.pop_front().unwrap_or(0)),
+            4,
+            COUNT_COPIES,
+        ));
+        if won_copies.len() < matches {
+            won_copies.resize(matches, 0);
+        }
+        for card_copies in won_copies.iter_mut().take(matches) {
+            *card_copies = checked::expect(card_copies.checked_add(copies), 4, COUNT_COPIES);
+        }
+        trace_event!(
+            "card_copies",
+            card = card.id,
+            matches = matches,
+            copies = copies
+        );
+        checked::expect(sum.checked_add(copies), 4, COUNT_COPIES)
+    })
+}
+
+pub fn part_a(input: &str) -> u64 {
+    solve_part_a(&parse(input))
+}
+
+pub fn part_b(input: &str) -> u64 {
+    solve_part_b(&parse(input))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_test_file;
+    use crate::read_test_file;
+    use crate::trace::record_events;
+    use itertools::Itertools;
 
     #[test]
     fn test_scratchcard_from_str() {
@@ -95,21 +289,110 @@ mod tests {
         let actual = Scratchcard::from_str(input).unwrap();
         let expected = Scratchcard {
             id: 2,
-            winning_numbers: vec![13, 32],
+            winning_numbers: HashSet::from([13, 32]),
             drawn_numbers: vec![61, 30, 68, 9],
         };
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_trace_events() {
+        let input = read_test_file(4);
+        let (_, events) = record_events("card", || part_a(&input));
+        assert!(events.contains(&r#"{"event":"card","card":1,"points":8}"#.to_string()));
+        let (_, events) = record_events("card_copies", || part_b(&input));
+        for event in [
+            r#"{"event":"card_copies","card":4,"matches":1,"copies":8}"#,
+            r#"{"event":"card_copies","card":5,"matches":0,"copies":14}"#,
+        ] {
+            assert!(events.iter().any(|e| e == event), "no {}", event);
+        }
+    }
+
     #[test]
     fn test_part_a() {
         let input = read_test_file(4);
         assert_eq!(part_a(&input), 13);
     }
 
+    #[test]
+    fn test_scoring() {
+        let input = read_test_file(4);
+        let pile = parse(&input);
+        assert_eq!(solve_part_a_with(&pile, &"linear".parse().unwrap()), 9);
+        let table = Scoring::from_str("table:0,1,3").unwrap();
+        assert_eq!(table, Scoring::Table(vec![0, 1, 3]));
+        // 4, 2, 2 and 1 matches, the 4 scoring the table's last entry
+        assert_eq!(solve_part_a_with(&pile, &table), 10);
+        assert!(Scoring::from_str("squared").is_err());
+        assert!(Scoring::from_str("table:").is_err());
+        assert_eq!(Scoring::Table(vec![]).points(3), 0);
+    }
+
+    #[test]
+    fn test_copies() {
+        let input = read_test_file(4);
+        let pile = parse(&input);
+        assert_eq!(pile.copies(&CopyRules::default()), vec![1, 2, 4, 8, 14, 1]);
+
+        let capped = CopyRules {
+            cap: Some(3),
+            wrap_around: false,
+        };
+        assert_eq!(pile.copies(&capped), vec![1, 2, 3, 3, 3, 1]);
+        assert_eq!(stream_part_b_with(input.as_bytes(), &capped), 13);
+
+        let no_copies = CopyRules {
+            cap: Some(0),
+            wrap_around: false,
+        };
+        assert_eq!(pile.copies(&no_copies), vec![0; 6]);
+        assert_eq!(stream_part_b_with(input.as_bytes(), &no_copies), 0);
+        let wrapping = CopyRules {
+            wrap_around: true,
+            ..no_copies
+        };
+        assert_eq!(solve_part_b_with(&pile, &wrapping), 0);
+    }
+
+    #[test]
+    fn test_wrap_around() {
+        let rules = CopyRules {
+            cap: None,
+            wrap_around: true,
+        };
+        let pile = parse("Card 1: 1 | 2\nCard 2: 1 | 1");
+        assert_eq!(pile.copies(&rules), vec![2, 1]);
+
+        // the two cards keep winning each other until both reach the cap
+        let pile = parse("Card 1: 1 | 1\nCard 2: 1 | 1");
+        let rules = CopyRules {
+            cap: Some(5),
+            ..rules
+        };
+        assert_eq!(pile.copies(&rules), vec![5, 5]);
+    }
+
     #[test]
     fn test_part_b() {
         let input = read_test_file(4);
         assert_eq!(part_b(&input), 30);
     }
+
+    #[test]
+    #[should_panic(expected = "Overflow on day 4 while counting card copies")]
+    fn test_copy_overflow() {
+        // every card wins a copy of the next two, so the copies grow like the Fibonacci sequence
+        let input = (1..=100)
+            .map(|id| format!("Card {}: 1 2 | 1 2 3", id))
+            .join("\n");
+        part_b(&input);
+    }
+
+    #[test]
+    fn test_stream() {
+        let input = read_test_file(4);
+        assert_eq!(stream_part_a(input.as_bytes()), 13);
+        assert_eq!(stream_part_b(input.as_bytes()), 30);
+    }
 }
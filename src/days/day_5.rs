@@ -1,21 +1,39 @@
-use advent_of_code_2023::to_u64;
+use crate::{checked, to_u64, trace_event};
 use rayon::prelude::*;
 use std::str::FromStr;
 
+const MAP_ID: &str = "mapping an id through a range";
+const RETURN_LOWEST_LOCATION: &str = "returning the lowest location";
+const EXPAND_SEED_RANGE: &str = "expanding a seed range";
+
 #[derive(Debug, PartialEq)]
-struct Range {
-    destination_range_start: u64,
-    source_range_start: u64,
-    range_length: u64,
+pub struct Range {
+    pub destination_range_start: u64,
+    pub source_range_start: u64,
+    pub range_length: u64,
+}
+
+pub struct Almanac {
+    pub seeds: Vec<u64>,
+    /// one list of ranges per `x-to-y` map, in chain order
+    pub mappings: Vec<Vec<Range>>,
 }
 
 impl Range {
-    fn convert(&self, source: u64) -> Option<u64> {
-        let range_offset = source as i64 - self.source_range_start as i64;
-        if range_offset < 0 || range_offset >= self.range_length as i64 {
+    pub fn convert(&self, source: u64) -> Option<u64> {
+        if source < self.source_range_start {
             return None;
         }
-        Some(self.destination_range_start + range_offset as u64)
+        let range_offset = source - self.source_range_start;
+        if range_offset >= self.range_length {
+            return None;
+        }
+        Some(checked::add(
+            self.destination_range_start,
+            range_offset,
+            5,
+            MAP_ID,
+        ))
     }
 }
 
@@ -23,23 +41,26 @@ impl FromStr for Range {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let values = s.splitn(3, " ").map(|x| to_u64(x)).collect::<Vec<u64>>();
-
-        if values.len() != 3 {
-            return Err(());
+        let mut values = s.splitn(3, " ").map(to_u64);
+
+        match (values.next(), values.next(), values.next()) {
+            (Some(destination_range_start), Some(source_range_start), Some(range_length)) => {
+                Ok(Range {
+                    destination_range_start,
+                    source_range_start,
+                    range_length,
+                })
+            }
+            _ => Err(()),
         }
-        Ok(Range {
-            destination_range_start: values[0],
-            source_range_start: values[1],
-            range_length: values[2],
-        })
     }
 }
 
 fn mapping_str_to_mapping(mapping_str: &str) -> Vec<Range> {
-    let ranges_str = &mapping_str.trim().split("\n").collect::<Vec<&str>>()[1..];
-    ranges_str
-        .into_iter()
+    mapping_str
+        .trim()
+        .split("\n")
+        .skip(1)
         .map(|x| Range::from_str(x).unwrap())
         .collect::<Vec<Range>>()
 }
@@ -47,70 +68,87 @@ fn mapping_str_to_mapping(mapping_str: &str) -> Vec<Range> {
 fn build_mappings(body_str: &str) -> Vec<Vec<Range>> {
     body_str
         .split("\n\n")
-        .map(|x| mapping_str_to_mapping(x))
+        .map(mapping_str_to_mapping)
         .collect::<Vec<Vec<Range>>>()
 }
 
-fn convert_id_from_ranges(id: u64, ranges: &Vec<Range>) -> u64 {
+fn convert_id_from_ranges(id: u64, ranges: &[Range]) -> u64 {
     ranges.iter().find_map(|r| r.convert(id)).unwrap_or(id)
 }
 
-fn convert_id_from_mappings(seed_id: u64, mappings: &Vec<Vec<Range>>) -> u64 {
+fn convert_id_from_mappings(seed_id: u64, mappings: &[Vec<Range>]) -> u64 {
     mappings
         .iter()
         .fold(seed_id, |id, ranges| convert_id_from_ranges(id, ranges))
 }
 
+fn lowest_location(locations: Vec<u64>) -> i64 {
+    let lowest = locations.into_iter().min().unwrap();
+    checked::expect(i64::try_from(lowest).ok(), 5, RETURN_LOWEST_LOCATION)
+}
+
 fn create_range(start_id: u64, range: u64) -> Vec<u64> {
-    (start_id..(start_id + range)).collect::<Vec<u64>>()
+    let end_id = checked::expect(start_id.checked_add(range), 5, EXPAND_SEED_RANGE);
+    (start_id..end_id).collect::<Vec<u64>>()
 }
 
-pub fn part_a(input: &str) -> i64 {
+pub fn parse(input: &str) -> Almanac {
     let (seed_str, body_str) = input.split_once("\n").unwrap();
-    let seed_ids = seed_str
-        .replace("seeds: ", "")
-        .trim()
-        .split(" ")
-        .map(|x| to_u64(x))
+    let seeds = seed_str
+        .trim_start_matches("seeds:")
+        .split_ascii_whitespace()
+        .map(to_u64)
         .collect::<Vec<u64>>();
-    let mappings = build_mappings(body_str);
+    Almanac {
+        seeds,
+        mappings: build_mappings(body_str),
+    }
+}
 
-    let final_ids = seed_ids
+pub fn solve_part_a(almanac: &Almanac) -> i64 {
+    let final_ids = almanac
+        .seeds
         .iter()
-        .map(|&seed_id| convert_id_from_mappings(seed_id, &mappings))
+        .map(|&seed_id| {
+            let location = convert_id_from_mappings(seed_id, &almanac.mappings);
+            trace_event!("seed", seed = seed_id, location = location);
+            location
+        })
         .collect::<Vec<u64>>();
 
-    final_ids.into_iter().min().unwrap() as i64
+    lowest_location(final_ids)
 }
 
-pub fn part_b(input: &str) -> i64 {
-    let (seed_str, body_str) = input.split_once("\n").unwrap();
-    let seed_row = seed_str
-        .replace("seeds: ", "")
-        .trim()
-        .split(" ")
-        .map(|x| to_u64(x))
-        .collect::<Vec<u64>>();
+pub fn solve_part_b(almanac: &Almanac) -> i64 {
+    let seed_row = &almanac.seeds;
+    for pair in seed_row.chunks(2) {
+        trace_event!("seed_range", start = pair[0], length = pair[1]);
+    }
     let seed_ids = seed_row
         .chunks(2)
-        .map(|pair| create_range(pair[0], pair[1]))
-        .flatten()
+        .flat_map(|pair| create_range(pair[0], pair[1]))
         .collect::<Vec<u64>>();
 
-    let mappings = build_mappings(body_str);
-
     let final_ids = seed_ids
         .par_iter()
-        .map(|&seed_id| convert_id_from_mappings(seed_id, &mappings))
+        .map(|&seed_id| convert_id_from_mappings(seed_id, &almanac.mappings))
         .collect::<Vec<u64>>();
 
-    final_ids.into_iter().min().unwrap() as i64
+    lowest_location(final_ids)
+}
+
+pub fn part_a(input: &str) -> i64 {
+    solve_part_a(&parse(input))
+}
+
+pub fn part_b(input: &str) -> i64 {
+    solve_part_b(&parse(input))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_test_file;
+    use crate::read_test_file;
 
     #[test]
     fn test_range_from_str() {
@@ -1,47 +1,280 @@
-use advent_of_code_2023::to_u64;
+use crate::{checked, to_u64, trace_event};
+use std::str::FromStr;
 
-struct Race {
-    time: u64,
-    distance: u64,
+const READ_LONG_RACE: &str = "reading the long race";
+const MULTIPLY_WAYS_TO_WIN: &str = "multiplying the ways to win";
+const RETURN_WAYS_TO_WIN: &str = "returning the ways to win";
+
+pub struct Race {
+    pub time: u64,
+    pub distance: u64,
+}
+
+pub struct RaceSheet {
+    /// part a reads every column as its own race
+    pub races: Vec<Race>,
+    /// part b ignores the spaces and reads a single long race
+    pub long_race: Race,
+}
+
+/// how fast the boat goes once the button has been held for a while
+#[derive(Clone, Debug, PartialEq)]
+pub enum Acceleration {
+    /// `rate * hold^exponent`, the puzzle's boats having rate 1 and exponent 1
+    Polynomial { rate: u64, exponent: u32 },
+    /// `base^hold`
+    Exponential { base: u64 },
+}
+
+/// reads "linear", "linear:3" (the rate), "power:2" (the exponent) or "exponential:2" (the base)
+impl FromStr for Acceleration {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s.split_once(":").unwrap_or((s, "1"));
+        let value = value
+            .parse::<u64>()
+            .map_err(|_| format!("invalid number [{}] in acceleration [{}]", value, s))?;
+        match kind {
+            "linear" => Ok(Acceleration::Polynomial {
+                rate: value,
+                exponent: 1,
+            }),
+            "power" => Ok(Acceleration::Polynomial {
+                rate: 1,
+                exponent: u32::try_from(value)
+                    .map_err(|_| format!("exponent {} is too large", value))?,
+            }),
+            "exponential" => Ok(Acceleration::Exponential { base: value }),
+            _ => Err(format!(
+                "unrecognised acceleration [{}], expected linear, power or exponential",
+                s
+            )),
+        }
+    }
+}
+
+/// How a boat's speed depends on the hold time. The distance travelled rises
+/// to a single peak and falls away again for every profile, which is what lets
+/// the winning hold times be searched for on either side of the peak.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RaceModel {
+    pub acceleration: Acceleration,
+    /// fastest the boat can go, however long the button is held
+    pub max_speed: Option<u64>,
+}
+
+/// the puzzle's boats, one millimetre per millisecond faster for every millisecond held
+impl Default for RaceModel {
+    fn default() -> Self {
+        RaceModel {
+            acceleration: Acceleration::Polynomial {
+                rate: 1,
+                exponent: 1,
+            },
+            max_speed: None,
+        }
+    }
+}
+
+/// the hold times that beat a race's record and the best one
+pub struct RaceOutcome {
+    /// shortest and longest winning hold times, None when no hold wins
+    pub winning_holds: Option<(u64, u64)>,
+    pub optimal_hold: u64,
+    /// whether the answer was solved for directly rather than searched for
+    pub exact: bool,
+}
+
+impl RaceOutcome {
+    pub fn ways_to_win(&self) -> u64 {
+        self.winning_holds
+            .map_or(0, |(shortest, longest)| longest - shortest + 1)
+    }
+}
+
+impl RaceModel {
+    /// speed after holding for `hold` ms, saturating rather than overflowing
+    pub fn speed(&self, hold: u64) -> u128 {
+        let speed = match self.acceleration {
+            Acceleration::Polynomial { rate, exponent } => (hold as u128)
+                .checked_pow(exponent)
+                .and_then(|power| power.checked_mul(rate as u128)),
+            Acceleration::Exponential { base } => u32::try_from(hold)
+                .ok()
+                .and_then(|hold| (base as u128).checked_pow(hold)),
+        };
+        let speed = speed.unwrap_or(u128::MAX);
+        self.max_speed
+            .map_or(speed, |max| u128::min(speed, max as u128))
+    }
+
+    /// how far the boat travels in a race of `time` ms after holding for `hold` ms,
+    /// nowhere if it's held for the whole race or longer
+    pub fn distance(&self, hold: u64, time: u64) -> u128 {
+        self.speed(hold)
+            .saturating_mul(time.saturating_sub(hold) as u128)
+    }
+
+    /// the rate and exponent of profiles that are a plain polynomial
+    fn polynomial(&self) -> Option<(u64, u32)> {
+        match (&self.acceleration, self.max_speed) {
+            (&Acceleration::Polynomial { rate, exponent }, None) => Some((rate, exponent)),
+            _ => None,
+        }
+    }
+
+    /// the hold time that goes furthest, the shortest one on a tie
+    pub fn optimal_hold(&self, time: u64) -> u64 {
+        let peak = match self.polynomial() {
+            // d/dh of h^k * (t - h) is zero at h = kt / (k + 1)
+            Some((_, exponent)) => {
+                (exponent as u128 * time as u128 / (exponent as u128 + 1)) as u64
+            }
+            // the first hold after which going any longer doesn't help
+            None => partition_point(0, time, |hold| {
+                self.distance(hold + 1, time) > self.distance(hold, time)
+            }),
+        };
+        if peak < time && self.distance(peak + 1, time) > self.distance(peak, time) {
+            peak + 1
+        } else {
+            peak
+        }
+    }
+
+    /// The roots of `rate * h * (time - h) = target` for linear profiles, moved
+    /// onto the first and last whole hold times that reach the target.
+    fn solve_linear(&self, time: u64, target: u128, peak: u64) -> Option<(u64, u64)> {
+        match self.polynomial() {
+            Some((rate, 1)) if rate > 0 => {
+                let (x1, x2) =
+                    solve_quadratic(rate as f64, -(rate as f64 * time as f64), target as f64)?;
+                let reaches = |hold: u64| self.distance(hold, time) >= target;
+                let mut shortest = (x1.ceil().max(0.0) as u64).min(peak);
+                let mut longest = (x2.floor().max(0.0) as u64).clamp(peak, time);
+                while shortest > 0 && reaches(shortest - 1) {
+                    shortest -= 1;
+                }
+                while !reaches(shortest) {
+                    shortest += 1;
+                }
+                while longest < time && reaches(longest + 1) {
+                    longest += 1;
+                }
+                while !reaches(longest) {
+                    longest -= 1;
+                }
+                Some((shortest, longest))
+            }
+            _ => None,
+        }
+    }
+
+    /// Which hold times beat the race's record. Linear profiles are solved with
+    /// the quadratic formula, anything else is binary searched either side of
+    /// the optimal hold time.
+    pub fn outcome(&self, race: &Race) -> RaceOutcome {
+        let target = race.distance as u128 + 1;
+        let optimal_hold = self.optimal_hold(race.time);
+        if self.distance(optimal_hold, race.time) < target {
+            // a polynomial's peak is solved for, so finding nothing wins takes no search
+            return RaceOutcome {
+                winning_holds: None,
+                optimal_hold,
+                exact: self.polynomial().is_some(),
+            };
+        }
+        if let Some(winning_holds) = self.solve_linear(race.time, target, optimal_hold) {
+            return RaceOutcome {
+                winning_holds: Some(winning_holds),
+                optimal_hold,
+                exact: true,
+            };
+        }
+
+        let shortest = partition_point(0, optimal_hold, |hold| {
+            self.distance(hold, race.time) < target
+        });
+        let longest = partition_point(optimal_hold, race.time, |hold| {
+            self.distance(hold, race.time) >= target
+        }) - 1;
+        RaceOutcome {
+            winning_holds: Some((shortest, longest)),
+            optimal_hold,
+            exact: false,
+        }
+    }
+
+    pub fn ways_to_win(&self, race: &Race) -> u64 {
+        let outcome = self.outcome(race);
+        trace_event!(
+            "race",
+            time = race.time,
+            distance = race.distance,
+            shortest_hold = outcome.winning_holds.map(|(shortest, _)| shortest),
+            longest_hold = outcome.winning_holds.map(|(_, longest)| longest),
+            optimal_hold = outcome.optimal_hold,
+            ways_to_win = outcome.ways_to_win()
+        );
+        outcome.ways_to_win()
+    }
+}
+
+/// the first value in `start..=end` that `pred` is false for, given `pred`
+/// is true up to some point and false after it (`end + 1` if it never is)
+fn partition_point(start: u64, end: u64, pred: impl Fn(u64) -> bool) -> u64 {
+    let (mut low, mut high) = (start, end + 1);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if mid <= end && pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
 }
 
 impl Race {
-    fn record_beating_permutations(&self) -> u64 {
-        let distance_target = (self.distance + 1) as f64;
-        let time_to_race = -1.0 * self.time as f64;
-        let (x1, x2) = solve_quadratic(1.0, time_to_race, distance_target);
-        let lower_bound = f64::min(x1, x2).ceil() as u64;
-        let upper_bound = f64::max(x1, x2) as u64;
-        upper_bound - lower_bound + 1
+    pub fn record_beating_permutations(&self) -> u64 {
+        RaceModel::default().ways_to_win(self)
     }
 }
 
-fn solve_quadratic(a: f64, b: f64, c: f64) -> (f64, f64) {
+/// the real roots of `ax^2 + bx + c`, smallest first
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
     let discriminant = b.powi(2) - 4.0 * a * c;
-    if discriminant <= 0.0 {
-        panic!("Unable to find a valid solution");
+    if discriminant < 0.0 {
+        return None;
     }
     let sqrt_discriminant = discriminant.sqrt();
-    let x1 = (-b + sqrt_discriminant) / (2.0 * a);
-    let x2 = (-b - sqrt_discriminant) / (2.0 * a);
-    (x1, x2)
+    let x1 = (-b - sqrt_discriminant) / (2.0 * a);
+    let x2 = (-b + sqrt_discriminant) / (2.0 * a);
+    Some((x1, x2))
 }
 
 fn to_numeric_list(raw_str: &str, label: &str) -> Vec<u64> {
-    let cleaned_str = raw_str.replace(label, "").trim().to_string();
-    cleaned_str
-        .split(" ")
-        .filter(|x| x.len() != 0)
-        .map(|x| to_u64(x))
+    raw_str
+        .trim_start_matches(label)
+        .split_ascii_whitespace()
+        .map(to_u64)
         .collect::<Vec<u64>>()
 }
 
+/// reads every digit after the label as one number, ignoring the spaces
 fn to_number(raw_str: &str, label: &str) -> u64 {
-    let cleaned_str = raw_str.replace(label, "").trim().to_string();
-    to_u64(&cleaned_str.replace(" ", ""))
+    raw_str
+        .trim_start_matches(label)
+        .bytes()
+        .filter(|b| b.is_ascii_digit())
+        .fold(0, |acc, b| {
+            let shifted = checked::mul(acc, 10, 6, READ_LONG_RACE);
+            checked::add(shifted, (b - b'0') as u64, 6, READ_LONG_RACE)
+        })
 }
 
-pub fn part_a(input: &str) -> i64 {
+pub fn parse(input: &str) -> RaceSheet {
     let (time_str, distance_str) = input.split_once("\n").unwrap();
     let times = to_numeric_list(time_str, "Time:");
     let distances = to_numeric_list(distance_str, "Distance:");
@@ -54,27 +287,48 @@ pub fn part_a(input: &str) -> i64 {
             distance: d,
         })
         .collect::<Vec<Race>>();
+    let long_race = Race {
+        time: to_number(time_str, "Time:"),
+        distance: to_number(distance_str, "Distance:"),
+    };
+    RaceSheet { races, long_race }
+}
 
-    let race_permutations = races
-        .iter()
-        .map(|r| r.record_beating_permutations())
-        .collect::<Vec<u64>>();
-    race_permutations.iter().product::<u64>() as i64
+fn to_answer(ways_to_win: u64) -> i64 {
+    checked::expect(i64::try_from(ways_to_win).ok(), 6, RETURN_WAYS_TO_WIN)
+}
+
+pub fn solve_part_a(race_sheet: &RaceSheet) -> i64 {
+    solve_part_a_with(race_sheet, &RaceModel::default())
+}
+
+/// part a with boats that speed up some other way
+pub fn solve_part_a_with(race_sheet: &RaceSheet, model: &RaceModel) -> i64 {
+    let ways_to_win = race_sheet.races.iter().map(|r| model.ways_to_win(r));
+    to_answer(checked::product(ways_to_win, 6, MULTIPLY_WAYS_TO_WIN))
+}
+
+pub fn solve_part_b(race_sheet: &RaceSheet) -> i64 {
+    solve_part_b_with(race_sheet, &RaceModel::default())
+}
+
+/// part b with boats that speed up some other way
+pub fn solve_part_b_with(race_sheet: &RaceSheet, model: &RaceModel) -> i64 {
+    to_answer(model.ways_to_win(&race_sheet.long_race))
+}
+
+pub fn part_a(input: &str) -> i64 {
+    solve_part_a(&parse(input))
 }
 
 pub fn part_b(input: &str) -> i64 {
-    let (time_str, distance_str) = input.split_once("\n").unwrap();
-    let race = Race {
-        time: to_number(time_str, "Time:"),
-        distance: to_number(distance_str, "Distance:"),
-    };
-    race.record_beating_permutations() as i64
+    solve_part_b(&parse(input))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_test_file;
+    use crate::read_test_file;
 
     #[test]
     fn test_race_record_beating_permutations() {
@@ -85,6 +339,64 @@ mod tests {
         assert_eq!(race.record_beating_permutations(), 9)
     }
 
+    #[test]
+    fn test_linear_matches_search() {
+        let race = Race {
+            time: 71530,
+            distance: 940200,
+        };
+        let exact = RaceModel::default().outcome(&race);
+        assert!(exact.exact);
+        assert_eq!(exact.winning_holds, Some((14, 71516)));
+        assert_eq!(exact.optimal_hold, 35765);
+
+        // a max speed too high to reach forces the search without changing the answer
+        let searched = RaceModel {
+            max_speed: Some(u64::MAX),
+            ..RaceModel::default()
+        }
+        .outcome(&race);
+        assert!(!searched.exact);
+        assert_eq!(searched.winning_holds, exact.winning_holds);
+        assert_eq!(searched.optimal_hold, exact.optimal_hold);
+    }
+
+    #[test]
+    fn test_acceleration_profiles() {
+        let race = Race {
+            time: 7,
+            distance: 9,
+        };
+        let model = |acceleration: &str, max_speed| RaceModel {
+            acceleration: Acceleration::from_str(acceleration).unwrap(),
+            max_speed,
+        };
+        // distances 0, 12, 20, 24, 24, 20, 12, 0
+        assert_eq!(
+            model("linear:2", None).outcome(&race).winning_holds,
+            Some((1, 6))
+        );
+        // distances 0, 6, 20, 36, 48, 50, 36, 0
+        let power = model("power:2", None).outcome(&race);
+        assert_eq!((power.winning_holds, power.optimal_hold), (Some((2, 6)), 5));
+        // only linear boats have their winning holds solved for, the rest are searched
+        assert!(!power.exact);
+        // distances 7, 12, 20, 32, 24, 16, 8, 0 with the speed topping out at 8
+        let exponential = model("exponential:2", Some(8)).outcome(&race);
+        assert_eq!(exponential.winning_holds, Some((1, 5)));
+        assert_eq!(exponential.optimal_hold, 3);
+        assert_eq!(model("linear", Some(1)).outcome(&race).ways_to_win(), 0);
+        // distances 5, 12, 27, 54, 81, 0, the search reaching the end of the race
+        let short_race = Race {
+            time: 5,
+            distance: 1,
+        };
+        let exponential = model("exponential:3", None).outcome(&short_race);
+        assert_eq!(exponential.winning_holds, Some((0, 4)));
+        assert_eq!(exponential.optimal_hold, 4);
+        assert!(Acceleration::from_str("cubic").is_err());
+    }
+
     #[test]
     fn test_to_number() {
         let raw_str = "Distance:  9  40  200";
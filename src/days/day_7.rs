@@ -1,38 +1,152 @@
 use crate::days::day_7::Part::{PartA, PartB};
-use advent_of_code_2023::to_u32;
+use crate::{checked, fold_lines, to_u32, trace_event};
 use itertools::Itertools;
 use std::cmp::Ordering;
-use std::convert::TryInto;
+use std::io::BufRead;
+use std::str::FromStr;
+
+const MULTIPLY_BID: &str = "multiplying a bid by its rank";
+const SUM_WINNINGS: &str = "summing the winnings";
+
+const CATEGORY_NAMES: [&str; 7] = [
+    "high card",
+    "one pair",
+    "two pair",
+    "three of a kind",
+    "full house",
+    "four of a kind",
+    "five of a kind",
+];
 
 #[derive(Copy, Clone)]
-enum Part {
+enum Part<'r> {
     PartA,
-    PartB,
+    PartB(&'r JokerRules),
+}
+
+/// a hand's cards as ranks, 2 to 14 with jokers as 1
+pub type Cards = [u32; 5];
+
+const JOKER: u32 = 1;
+/// every card a joker could stand in for, lowest first
+const NON_JOKER_RANKS: [u32; 12] = [2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 13, 14];
+
+/// a hand as it appears in the input, before its cards are ranked for a part
+pub struct DealtHand<'a> {
+    pub cards: &'a str,
+    pub bid: u32,
+}
+
+impl DealtHand<'_> {
+    /// the hand ranked as part a plays it, or as part b does with `jokers`
+    pub fn rank(&self, jokers: Option<&JokerRules>) -> Hand {
+        rank_hand(self, jokers.map_or(PartA, PartB))
+    }
 }
 
-type Cards = [u32; 5];
+pub struct CamelCards<'a> {
+    pub hands: Vec<DealtHand<'a>>,
+}
 
+/// which cards the jokers in a part b hand are allowed to become
+#[derive(Clone, Debug, PartialEq)]
+pub struct JokerRules {
+    /// ranks a joker may stand in for, a joker with none left staying a joker
+    pub cards: Vec<u32>,
+}
+
+/// the puzzle's jokers, which can be any card
+impl Default for JokerRules {
+    fn default() -> Self {
+        JokerRules {
+            cards: NON_JOKER_RANKS.to_vec(),
+        }
+    }
+}
+
+/// reads the cards a joker may become, e.g. "23456789T" for only number cards
+impl FromStr for JokerRules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cards = s
+            .chars()
+            .map(|c| match c {
+                '2'..='9' | 'T' | 'Q' | 'K' | 'A' => Ok(card_char_to_rank(c, PartA)),
+                _ => Err(format!("a joker can't become [{}]", c)),
+            })
+            .collect::<Result<Vec<u32>, String>>()?;
+        Ok(JokerRules { cards })
+    }
+}
+
+/// a dealt hand with its cards ranked for a part, ordered by how strong it is
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-struct Hand {
-    cards: Cards,
-    bid: u32,
-    score_func: fn(Cards) -> u32,
+pub struct Hand {
+    pub cards: Cards,
+    pub bid: u32,
+    /// the hand's category, 0 for high card up to 6 for five of a kind
+    pub category: u32,
+    /// the cards the hand is played as once its jokers are assigned
+    pub played_as: Cards,
 }
 
-impl Hand {
-    fn score(&self) -> u32 {
-        (self.score_func)(self.cards)
+/// The hands the jokers in `cards` could become under `rules`. Cards the hand
+/// doesn't already hold can only make a category by matching each other, so the
+/// highest of them stands in for all of them, and as jokers are interchangeable
+/// each multiset of cards for them is only listed once.
+fn joker_assignments(cards: Cards, rules: &JokerRules) -> Vec<Cards> {
+    let joker_idxs = (0..cards.len())
+        .filter(|&idx| cards[idx] == JOKER)
+        .collect_vec();
+    let mut candidates = cards
+        .iter()
+        .copied()
+        .filter(|card| *card != JOKER && rules.cards.contains(card))
+        .unique()
+        .collect_vec();
+    if let Some(&absent) = rules
+        .cards
+        .iter()
+        .filter(|card| !cards.contains(card))
+        .max()
+    {
+        candidates.push(absent);
+    }
+    if joker_idxs.is_empty() || candidates.is_empty() {
+        return vec![cards];
     }
+
+    candidates
+        .into_iter()
+        .combinations_with_replacement(joker_idxs.len())
+        .map(|choice| {
+            let mut hand = cards;
+            for (&idx, card) in joker_idxs.iter().zip(choice) {
+                hand[idx] = card;
+            }
+            hand
+        })
+        .collect_vec()
 }
 
-fn part_a_score(cards: Cards) -> u32 {
-    calculate_score(&cards.to_vec(), 0)
+/// the assignment of jokers with the best category, and the highest cards on a tie
+fn best_joker_assignment(cards: Cards, rules: &JokerRules) -> (Cards, u32) {
+    joker_assignments(cards, rules)
+        .into_iter()
+        .map(|hand| (hand, calculate_score(&hand, 0)))
+        .max_by_key(|&(hand, category)| (category, hand))
+        .unwrap()
 }
 
-fn part_b_score(cards: Cards) -> u32 {
-    let cards_without_jokers = cards.into_iter().filter(|&x| x != 1).collect::<Vec<u32>>();
-    let joker_count = cards.len() - cards_without_jokers.len();
-    calculate_score(&cards_without_jokers, joker_count)
+/// The best hand the jokers (`J`) in `cards` can make under `rules`, along with
+/// its category, e.g. "KTJJT" is played as "KTTTT", four of a kind.
+pub fn best_hand(cards: &str, rules: &JokerRules) -> (String, &'static str) {
+    let hand = rank_hand(&DealtHand { cards, bid: 0 }, PartB(rules));
+    (
+        cards_to_string(hand.played_as),
+        CATEGORY_NAMES[hand.category as usize],
+    )
 }
 
 impl PartialOrd<Self> for Hand {
@@ -43,43 +157,22 @@ impl PartialOrd<Self> for Hand {
 
 impl Ord for Hand {
     fn cmp(&self, other: &Self) -> Ordering {
-        let self_score = self.score();
-        let other_score = other.score();
-        match self_score.cmp(&other_score) {
-            Ordering::Equal => {
-                // zip the two hands together into pairs
-                let pairs = self
-                    .cards
-                    .iter()
-                    .zip(other.cards.iter())
-                    .collect::<Vec<(&u32, &u32)>>();
-                // find the first pair where one card is different to the other
-                let comparison = pairs
-                    .iter()
-                    .find_map(|&(s, o)| {
-                        if s.cmp(o).is_eq() {
-                            None
-                        } else {
-                            Some(s.cmp(o))
-                        }
-                    })
-                    .unwrap();
-                comparison
-            }
-            ordering => ordering,
-        }
+        // ties are broken card by card on the hand as dealt, jokers and all
+        self.category
+            .cmp(&other.category)
+            .then_with(|| self.cards.cmp(&other.cards))
     }
 }
 
 fn card_char_to_rank(c: char, part: Part) -> u32 {
-    if c.is_digit(10) {
+    if c.is_ascii_digit() {
         return c.to_digit(10).unwrap();
     }
     match c {
         'T' => 10,
         'J' => match part {
             PartA => 11,
-            PartB => 1,
+            PartB(_) => 1,
         },
         'Q' => 12,
         'K' => 13,
@@ -88,85 +181,162 @@ fn card_char_to_rank(c: char, part: Part) -> u32 {
     }
 }
 
-fn calculate_score(cards: &Vec<u32>, joker_count: usize) -> u32 {
-    let mut card_counts = cards
-        .iter()
-        .sorted()
-        .group_by(|&&item| item)
-        .into_iter()
-        .map(|(_, group)| group.count())
-        .sorted()
-        .rev()
-        .collect::<Vec<usize>>();
+fn rank_to_card_char(rank: u32) -> char {
+    match rank {
+        1 | 11 => 'J',
+        10 => 'T',
+        12 => 'Q',
+        13 => 'K',
+        14 => 'A',
+        _ => char::from_digit(rank, 10).unwrap(),
+    }
+}
 
-    if card_counts.len() > 0 {
-        card_counts[0] += joker_count;
-    } else {
-        card_counts = vec![joker_count]
+fn calculate_score(cards: &[u32], joker_count: usize) -> u32 {
+    let mut card_counts = [0usize; 15];
+    for &card in cards {
+        card_counts[card as usize] += 1;
     }
+    card_counts.sort_unstable_by(|a, b| b.cmp(a));
 
-    match card_counts.as_slice() {
-        &[5] => 6,          // 5 of a kind
-        &[4, 1] => 5,       // 4 of a kind
-        &[3, 2] => 4,       // full house
-        &[3, 1, 1] => 3,    // three of a kind
-        &[2, 2, 1] => 2,    // two pair
-        &[2, 1, 1, 1] => 1, // pair
-        _ => 0,             // high card
+    match (card_counts[0] + joker_count, card_counts[1]) {
+        (5, _) => 6, // 5 of a kind
+        (4, _) => 5, // 4 of a kind
+        (3, 2) => 4, // full house
+        (3, _) => 3, // three of a kind
+        (2, 2) => 2, // two pair
+        (2, _) => 1, // pair
+        _ => 0,      // high card
     }
 }
 
-fn sort_rank_and_sum_bids(hands: &Vec<Hand>) -> u32 {
+fn cards_to_string(cards: Cards) -> String {
+    cards.map(rank_to_card_char).iter().collect::<String>()
+}
+
+fn sort_rank_and_sum_bids(hands: &[Hand]) -> u64 {
     let sorted_hands = hands.iter().cloned().sorted().collect::<Vec<Hand>>();
 
-    sorted_hands
-        .iter()
-        .enumerate()
-        .fold(0, |acc, (idx, &hand)| acc + ((idx as u32 + 1) * hand.bid))
-}
+    for (idx, hand) in sorted_hands.iter().enumerate() {
+        trace_event!(
+            "hand",
+            cards = cards_to_string(hand.cards),
+            played_as = cards_to_string(hand.played_as),
+            category = CATEGORY_NAMES[hand.category as usize],
+            rank = idx + 1,
+            bid = hand.bid
+        );
+    }
 
-fn hand_from_str(s: &str, part: Part) -> Hand {
-    let score_func = match part {
-        PartA => part_a_score,
-        PartB => part_b_score,
-    };
+    let winnings = sorted_hands.iter().enumerate().map(|(idx, hand)| {
+        let rank = idx as u64 + 1;
+        checked::expect(rank.checked_mul(hand.bid as u64), 7, MULTIPLY_BID)
+    });
+    checked::sum(winnings, 7, SUM_WINNINGS)
+}
 
+fn dealt_hand_from_str(s: &str) -> DealtHand<'_> {
     let (cards_str, bid_str) = s.split_once(" ").unwrap();
-    let cards = cards_str
-        .chars()
-        .map(|c| card_char_to_rank(c, part))
-        .collect::<Vec<u32>>();
-    Hand {
-        cards: cards.try_into().unwrap(),
+    DealtHand {
+        cards: cards_str,
         bid: to_u32(bid_str),
-        score_func: score_func,
     }
 }
 
-pub fn part_a(input: &str) -> u32 {
-    let hands = input
-        .split("\n")
-        .map(|x| hand_from_str(x, PartA))
+fn rank_hand(dealt_hand: &DealtHand, part: Part) -> Hand {
+    if dealt_hand.cards.chars().count() != 5 {
+        panic!("Hand [{}] doesn't have 5 cards", dealt_hand.cards);
+    }
+    let mut cards: Cards = [0; 5];
+    for (rank, c) in cards.iter_mut().zip(dealt_hand.cards.bytes()) {
+        *rank = card_char_to_rank(c as char, part);
+    }
+    let (played_as, category) = match part {
+        PartA => (cards, calculate_score(&cards, 0)),
+        PartB(rules) => best_joker_assignment(cards, rules),
+    };
+    Hand {
+        cards,
+        bid: dealt_hand.bid,
+        category,
+        played_as,
+    }
+}
+
+fn rank_and_sum_bids(camel_cards: &CamelCards, part: Part) -> u64 {
+    let hands = camel_cards
+        .hands
+        .iter()
+        .map(|x| rank_hand(x, part))
         .collect::<Vec<Hand>>();
     sort_rank_and_sum_bids(&hands)
 }
 
-pub fn part_b(input: &str) -> u32 {
+pub fn parse(input: &str) -> CamelCards<'_> {
     let hands = input
         .split("\n")
-        .map(|x| hand_from_str(x, PartB))
-        .collect::<Vec<Hand>>();
+        .map(dealt_hand_from_str)
+        .collect::<Vec<DealtHand>>();
+    CamelCards { hands }
+}
+
+pub fn solve_part_a(camel_cards: &CamelCards) -> u64 {
+    rank_and_sum_bids(camel_cards, PartA)
+}
+
+pub fn solve_part_b(camel_cards: &CamelCards) -> u64 {
+    solve_part_b_with(camel_cards, &JokerRules::default())
+}
+
+/// part b with jokers that can only become some cards
+pub fn solve_part_b_with(camel_cards: &CamelCards, rules: &JokerRules) -> u64 {
+    rank_and_sum_bids(camel_cards, PartB(rules))
+}
+
+/// Ranking needs every hand, so streaming keeps the ranked cards and bid of
+/// each line rather than the lines themselves.
+fn stream_rank_and_sum_bids(reader: impl BufRead, part: Part) -> u64 {
+    let hands = fold_lines(reader, Vec::new(), |mut hands, line| {
+        hands.push(rank_hand(&dealt_hand_from_str(line), part));
+        hands
+    });
     sort_rank_and_sum_bids(&hands)
 }
 
+pub fn stream_part_a(reader: impl BufRead) -> u64 {
+    stream_rank_and_sum_bids(reader, PartA)
+}
+
+pub fn stream_part_b(reader: impl BufRead) -> u64 {
+    stream_part_b_with(reader, &JokerRules::default())
+}
+
+pub fn stream_part_b_with(reader: impl BufRead, rules: &JokerRules) -> u64 {
+    stream_rank_and_sum_bids(reader, PartB(rules))
+}
+
+pub fn part_a(input: &str) -> u64 {
+    solve_part_a(&parse(input))
+}
+
+pub fn part_b(input: &str) -> u64 {
+    solve_part_b(&parse(input))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_test_file;
+    use crate::read_test_file;
+    use crate::trace::record_events;
+    use proptest::prelude::*;
+
+    fn hand_from_str(s: &str, part: Part) -> Hand {
+        rank_hand(&dealt_hand_from_str(s), part)
+    }
 
     #[test]
     fn test_hand_ordering() {
-        let input = vec![
+        let input = [
             "32T3K 765",
             "T55J5 684",
             "KK677 28",
@@ -185,51 +355,131 @@ mod tests {
 
     #[test]
     fn test_part_a_hand_score() {
-        let full_house = Hand {
-            cards: [2, 2, 5, 5, 5],
-            bid: 123,
-            score_func: part_a_score,
-        };
-        assert_eq!(full_house.score(), 4);
-        let two_pair = Hand {
-            cards: [2, 2, 14, 14, 3],
-            bid: 123,
-            score_func: part_a_score,
-        };
-        assert_eq!(two_pair.score(), 2);
+        assert_eq!(calculate_score(&[2, 2, 5, 5, 5], 0), 4);
+        assert_eq!(calculate_score(&[2, 2, 14, 14, 3], 0), 2);
     }
 
     #[test]
     fn test_card_char_to_rank() {
+        let rules = JokerRules::default();
         assert_eq!(card_char_to_rank('3', PartA), 3);
         assert_eq!(card_char_to_rank('A', PartA), 14);
         assert_eq!(card_char_to_rank('J', PartA), 11);
-        assert_eq!(card_char_to_rank('J', PartB), 1);
+        assert_eq!(card_char_to_rank('J', PartB(&rules)), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Hand [32T3] doesn't have 5 cards")]
+    fn test_short_hand() {
+        hand_from_str("32T3 765", PartA);
+    }
+
+    #[test]
+    #[should_panic(expected = "Hand [32T3KA] doesn't have 5 cards")]
+    fn test_long_hand() {
+        hand_from_str("32T3KA 765", PartA);
     }
 
     #[test]
     fn test_hand_from_str() {
         let input = "32T3J 765";
         let part_a_actual = hand_from_str(input, PartA);
-        let part_b_expected = Hand {
+        let part_a_expected = Hand {
             cards: [3, 2, 10, 3, 11],
             bid: 765,
-            score_func: part_a_score,
+            category: 1,
+            played_as: [3, 2, 10, 3, 11],
         };
-        assert_eq!(part_a_actual, part_b_expected);
-        let part_a_actual = hand_from_str(input, PartB);
+        assert_eq!(part_a_actual, part_a_expected);
+        let part_b_actual = hand_from_str(input, PartB(&JokerRules::default()));
         let part_b_expected = Hand {
             cards: [3, 2, 10, 3, 1],
             bid: 765,
-            score_func: part_b_score,
+            category: 3,
+            played_as: [3, 2, 10, 3, 3],
         };
-        assert_eq!(part_a_actual, part_b_expected);
+        assert_eq!(part_b_actual, part_b_expected);
+
+        let dealt_hand = dealt_hand_from_str(input);
+        assert_eq!(dealt_hand.rank(None), part_a_expected);
+        assert_eq!(
+            dealt_hand.rank(Some(&JokerRules::default())),
+            part_b_expected
+        );
+    }
+
+    #[test]
+    fn test_best_hand() {
+        let rules = JokerRules::default();
+        let best = |cards| best_hand(cards, &rules);
+        assert_eq!(best("KTJJT"), ("KTTTT".to_string(), "four of a kind"));
+        assert_eq!(best("JJJJJ"), ("AAAAA".to_string(), "five of a kind"));
+        assert_eq!(best("2345J"), ("23455".to_string(), "one pair"));
+
+        // jokers that can only be number cards can't join the kings
+        let numbers_only = JokerRules::from_str("23456789T").unwrap();
+        assert_eq!(
+            best_hand("KKJ23", &numbers_only),
+            ("KK323".to_string(), "two pair")
+        );
+        // with nothing to become, jokers only match each other
+        let no_cards = JokerRules { cards: vec![] };
+        assert_eq!(
+            best_hand("JJ234", &no_cards),
+            ("JJ234".to_string(), "one pair")
+        );
+        assert!(JokerRules::from_str("JQ").is_err());
+    }
+
+    /// the original part b rule: every joker joins the largest group of cards
+    fn joker_shortcut_score(cards: Cards) -> u32 {
+        let without_jokers = cards.into_iter().filter(|&x| x != JOKER).collect_vec();
+        calculate_score(&without_jokers, cards.len() - without_jokers.len())
+    }
+
+    fn card_chars() -> impl Strategy<Value = String> {
+        proptest::collection::vec(proptest::sample::select("23456789TJQKA".as_bytes()), 5)
+            .prop_map(|cards| String::from_utf8(cards).unwrap())
+    }
+
+    proptest! {
+        #[test]
+        fn test_best_hand_matches_shortcut(cards in card_chars()) {
+            let hand = hand_from_str(&format!("{} 1", cards), PartB(&JokerRules::default()));
+            prop_assert_eq!(hand.category, joker_shortcut_score(hand.cards));
+            // the jokers are all assigned and every other card is left alone
+            for (&dealt, &played) in hand.cards.iter().zip(hand.played_as.iter()) {
+                prop_assert_ne!(played, JOKER);
+                prop_assert!(dealt == JOKER || dealt == played);
+            }
+            prop_assert_eq!(calculate_score(&hand.played_as, 0), hand.category);
+        }
+
+        #[test]
+        fn test_restricted_jokers_never_beat_shortcut(cards in card_chars(), allowed in card_chars()) {
+            let rules = JokerRules::from_str(&allowed.replace('J', "")).unwrap();
+            let hand = hand_from_str(&format!("{} 1", cards), PartB(&rules));
+            prop_assert!(hand.category <= joker_shortcut_score(hand.cards));
+        }
     }
 
     #[test]
     fn test_calculate_score() {
-        assert_eq!(calculate_score(&vec!(10, 8, 9), 2), 3);
-        assert_eq!(calculate_score(&Vec::new(), 5), 6);
+        assert_eq!(calculate_score(&[10, 8, 9], 2), 3);
+        assert_eq!(calculate_score(&[], 5), 6);
+    }
+
+    #[test]
+    fn test_trace_events() {
+        let input = read_test_file(7);
+        let (_, events) = record_events("hand", || part_b(&input));
+        // KTJJT plays its jokers as tens, which takes it from rank 2 to rank 5
+        for event in [
+            r#"{"event":"hand","cards":"32T3K","played_as":"32T3K","category":"one pair","rank":1,"bid":765}"#,
+            r#"{"event":"hand","cards":"KTJJT","played_as":"KTTTT","category":"four of a kind","rank":5,"bid":220}"#,
+        ] {
+            assert!(events.iter().any(|e| e == event), "no {}", event);
+        }
     }
 
     #[test]
@@ -243,4 +493,11 @@ mod tests {
         let input = read_test_file(7);
         assert_eq!(part_b(&input), 5905);
     }
+
+    #[test]
+    fn test_stream() {
+        let input = read_test_file(7);
+        assert_eq!(stream_part_a(input.as_bytes()), 6440);
+        assert_eq!(stream_part_b(input.as_bytes()), 5905);
+    }
 }
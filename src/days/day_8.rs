@@ -1,10 +1,15 @@
-use itertools::FoldWhile::{Continue, Done};
+use crate::{checked, parallel, trace_event};
 use itertools::Itertools;
-use num::integer::lcm;
+use num::integer::Integer;
 use std::collections::HashMap;
 
-#[derive(Debug)]
-enum Direction {
+const LCM_GHOST_STEPS: &str = "taking the lowest common multiple of the ghosts' steps";
+
+/// a node's index into the network's arrays, assigned in definition order
+pub type NodeId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
     Left,
     Right,
 }
@@ -19,128 +24,312 @@ impl From<char> for Direction {
     }
 }
 
-struct Node {
-    name: String,
-    left_ref: String,
-    right_ref: String,
+/// the direction list packed into a bitset, a set bit meaning right
+pub struct Directions {
+    bits: Vec<u64>,
+    len: usize,
 }
 
-impl Node {
-    fn next_node(&self, direction: &Direction) -> &str {
-        match direction {
-            Direction::Left => &self.left_ref,
-            Direction::Right => &self.right_ref,
-        }
+impl Directions {
+    pub fn len(&self) -> usize {
+        self.len
     }
 
-    fn is_start_node(&self) -> bool {
-        self.name.ends_with("A")
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
-    fn is_end_node(&self) -> bool {
-        self.name.ends_with("Z")
+    /// the direction taken on step `idx`, wrapping round the list
+    pub fn get(&self, idx: usize) -> Direction {
+        let idx = idx % self.len;
+        if (self.bits[idx / 64] >> (idx % 64)) & 1 == 1 {
+            Direction::Right
+        } else {
+            Direction::Left
+        }
     }
 }
 
-impl From<&str> for Node {
+impl From<&str> for Directions {
     fn from(s: &str) -> Self {
+        let mut bits = vec![0u64; s.len().div_ceil(64)];
+        for (idx, ch) in s.chars().enumerate() {
+            if Direction::from(ch) == Direction::Right {
+                bits[idx / 64] |= 1 << (idx % 64);
+            }
+        }
+        Directions { bits, len: s.len() }
+    }
+}
+
+/// a line of the network as it appears in the input
+pub struct Node<'a> {
+    pub name: &'a str,
+    pub left_ref: &'a str,
+    pub right_ref: &'a str,
+}
+
+impl<'a> From<&'a str> for Node<'a> {
+    fn from(s: &'a str) -> Self {
         // example: "BBB = (DDD, EEE)"
-        let (name, directions) = s.split_once(" = ").unwrap();
+        let (name, links) = s.split_once(" = ").unwrap();
+        let (left_ref, right_ref) = links
+            .trim_start_matches("(")
+            .trim_end_matches(")")
+            .split_once(", ")
+            .unwrap();
 
         Node {
-            name: name.to_string(),
-            left_ref: directions[1..4].to_string(),
-            right_ref: directions[6..9].to_string(),
+            name,
+            left_ref,
+            right_ref,
         }
     }
 }
 
-struct PuzzleData {
-    directions: Vec<Direction>,
-    node_map: HashMap<String, Node>,
+/// The network with every node name interned into a dense `NodeId`, so a
+/// step is an array lookup rather than a string hash.
+pub struct Network<'a> {
+    names: Vec<&'a str>,
+    ids: HashMap<&'a str, NodeId>,
+    left: Vec<NodeId>,
+    right: Vec<NodeId>,
+    start_nodes: Vec<bool>,
+    end_nodes: Vec<bool>,
 }
 
-impl PuzzleData {
-    fn new(input: &str) -> PuzzleData {
+impl<'a> Network<'a> {
+    pub fn new(nodes: &[Node<'a>]) -> Network<'a> {
+        let names = nodes.iter().map(|n| n.name).collect_vec();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, &name)| (name, id))
+            .collect::<HashMap<&str, NodeId>>();
+        let resolve = |node: &Node, name: &str| {
+            *ids.get(name).unwrap_or_else(|| {
+                panic!(
+                    "Unrecognised node [{}] referenced by node [{}]",
+                    name, node.name
+                )
+            })
+        };
+        let left = nodes.iter().map(|n| resolve(n, n.left_ref)).collect_vec();
+        let right = nodes.iter().map(|n| resolve(n, n.right_ref)).collect_vec();
+
+        Network {
+            start_nodes: names.iter().map(|n| n.ends_with("A")).collect_vec(),
+            end_nodes: names.iter().map(|n| n.ends_with("Z")).collect_vec(),
+            names,
+            ids,
+            left,
+            right,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: NodeId) -> &'a str {
+        self.names[id]
+    }
+
+    pub fn next_node(&self, id: NodeId, direction: Direction) -> NodeId {
+        match direction {
+            Direction::Left => self.left[id],
+            Direction::Right => self.right[id],
+        }
+    }
+
+    pub fn is_start_node(&self, id: NodeId) -> bool {
+        self.start_nodes[id]
+    }
+
+    pub fn is_end_node(&self, id: NodeId) -> bool {
+        self.end_nodes[id]
+    }
+
+    pub fn start_nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.len()).filter(|&id| self.is_start_node(id))
+    }
+}
+
+pub struct PuzzleData<'a> {
+    pub directions: Directions,
+    pub network: Network<'a>,
+}
+
+impl<'a> PuzzleData<'a> {
+    pub fn new(input: &'a str) -> PuzzleData<'a> {
         let (directions_str, network_str) = input.split_once("\n\n").unwrap();
-        let directions = directions_str.chars().map(Direction::from).collect_vec();
-        let nodes = network_str.split("\n").map_into::<Node>().collect_vec();
-        let node_map = nodes.into_iter().fold(HashMap::new(), |mut map, row| {
-            map.insert(row.name.clone(), row);
-            map
-        });
+        let nodes = network_str.split("\n").map(Node::from).collect_vec();
         PuzzleData {
-            directions,
-            node_map,
+            directions: Directions::from(directions_str),
+            network: Network::new(&nodes),
         }
     }
 }
 
-fn find_end_node_idx(start_node: &Node, puzzle_data: &PuzzleData) -> i64 {
-    let directions = &puzzle_data.directions;
-    let node_map = &puzzle_data.node_map;
-    let result =
-        directions
-            .iter()
-            .cycle()
-            .fold_while((start_node, 0), |(current_node, idx), direction| {
-                if current_node.is_end_node() {
-                    Done((current_node, idx))
-                } else {
-                    let next_node = node_map.get(current_node.next_node(direction)).unwrap();
-                    Continue((next_node, idx + 1))
-                }
-            });
-    match result {
-        Done((_end_nodes, idx)) => idx,
-        _ => panic!("Unexpected state!"),
-    }
-}
-
-pub fn part_a(input: &str) -> u32 {
-    let puzzle_data = PuzzleData::new(input);
-    let directions = puzzle_data.directions;
-    let result = directions
-        .iter()
-        .cycle()
-        .fold_while(("AAA", 0), |(node_name, idx), direction| {
-            if node_name == "ZZZ" {
-                Done((node_name, idx))
-            } else {
-                let next_node = puzzle_data
-                    .node_map
-                    .get(node_name)
-                    .unwrap()
-                    .next_node(direction);
-                Continue((next_node, idx + 1))
+/// walks from `start_node` until an end node is reached, starting `start_idx`
+/// steps into the direction list. Returns the end node and the steps taken.
+fn find_end_node(start_node: NodeId, start_idx: usize, puzzle_data: &PuzzleData) -> (NodeId, i64) {
+    let network = &puzzle_data.network;
+    let mut current_node = start_node;
+    let mut steps = 0;
+    while !network.is_end_node(current_node) {
+        let direction = puzzle_data.directions.get(start_idx + steps);
+        current_node = network.next_node(current_node, direction);
+        steps += 1;
+    }
+    (current_node, steps as i64)
+}
+
+/// Walks every ghost in lockstep, one direction at a time, until each has
+/// reached an end node. Returns each ghost's end node and the steps taken.
+fn find_end_nodes_in_lockstep(
+    start_nodes: &[NodeId],
+    puzzle_data: &PuzzleData,
+) -> Vec<(NodeId, i64)> {
+    let network = &puzzle_data.network;
+    let mut current_nodes = start_nodes.to_vec();
+    let mut end_nodes = vec![None; start_nodes.len()];
+    let mut remaining = start_nodes.len();
+    let mut steps = 0;
+    loop {
+        for (ghost, &node) in current_nodes.iter().enumerate() {
+            if end_nodes[ghost].is_none() && network.is_end_node(node) {
+                end_nodes[ghost] = Some((node, steps as i64));
+                remaining -= 1;
             }
-        });
-    match result {
-        Done((_node_name, idx)) => idx,
-        _ => panic!("Unexpected state!"),
+        }
+        if remaining == 0 {
+            return end_nodes.into_iter().flatten().collect_vec();
+        }
+        let direction = puzzle_data.directions.get(steps);
+        for node in current_nodes.iter_mut() {
+            *node = network.next_node(*node, direction);
+        }
+        steps += 1;
     }
 }
 
-pub fn part_b(input: &str) -> i64 {
-    let puzzle_data = PuzzleData::new(input);
-    let starting_nodes = puzzle_data
-        .node_map
-        .values()
-        .filter(|&v| v.is_start_node())
-        .collect_vec();
-    let end_node_idxs = starting_nodes
-        .iter()
-        .map(|&n| find_end_node_idx(n, &puzzle_data))
-        .collect_vec();
+/// how many steps it takes to get from an end node, reached after `end_idx`
+/// steps, back to an end node
+fn find_cycle_length(end_node: NodeId, end_idx: i64, puzzle_data: &PuzzleData) -> i64 {
+    let direction = puzzle_data.directions.get(end_idx as usize);
+    let next_node = puzzle_data.network.next_node(end_node, direction);
+    let (_, idx) = find_end_node(next_node, end_idx as usize + 1, puzzle_data);
+    idx + 1
+}
+
+pub fn parse(input: &str) -> PuzzleData<'_> {
+    PuzzleData::new(input)
+}
+
+/// the lowest common multiple of `a` and `b`, or None if it doesn't fit in an i64
+fn checked_lcm(a: i64, b: i64) -> Option<i64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / a.gcd(&b)).checked_mul(b)
+}
+
+pub fn solve_part_a(puzzle_data: &PuzzleData) -> u64 {
+    let network = &puzzle_data.network;
+    let end_node = network.id("ZZZ").unwrap();
+    let mut current_node = network.id("AAA").unwrap();
+    let mut steps = 0u64;
+    while current_node != end_node {
+        let direction = puzzle_data.directions.get(steps as usize);
+        current_node = network.next_node(current_node, direction);
+        steps += 1;
+    }
+    trace_event!("walk", from = "AAA", to = "ZZZ", steps = steps);
+    steps
+}
+
+pub fn solve_part_b(puzzle_data: &PuzzleData) -> i64 {
+    let network = &puzzle_data.network;
+    let start_nodes = network.start_nodes().collect_vec();
+    let end_nodes = if parallel::is_enabled() {
+        parallel::map(&start_nodes, |&start_node| {
+            find_end_node(start_node, 0, puzzle_data)
+        })
+    } else {
+        find_end_nodes_in_lockstep(&start_nodes, puzzle_data)
+    };
+
+    for (&start_node, &(end_node, idx)) in start_nodes.iter().zip(end_nodes.iter()) {
+        trace_event!(
+            "ghost",
+            start = network.name(start_node),
+            end = network.name(end_node),
+            steps = idx,
+            cycle_length = find_cycle_length(end_node, idx, puzzle_data)
+        );
+    }
 
     // find the lowest common multiple of each of the end states
-    end_node_idxs.iter().fold(1, |acc, &x| lcm(acc, x))
+    end_nodes.iter().fold(1, |acc, &(_, idx)| {
+        checked::expect(checked_lcm(acc, idx), 8, LCM_GHOST_STEPS)
+    })
+}
+
+pub fn part_a(input: &str) -> u64 {
+    solve_part_a(&parse(input))
+}
+
+pub fn part_b(input: &str) -> i64 {
+    solve_part_b(&parse(input))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_test_file;
+    use crate::read_test_file;
+    use crate::trace::record_events;
+
+    #[test]
+    fn test_directions_bitset() {
+        let pattern = "LR".repeat(40) + "R";
+        let directions = Directions::from(pattern.as_str());
+        assert_eq!(directions.len(), 81);
+        assert_eq!(directions.get(0), Direction::Left);
+        assert_eq!(directions.get(65), Direction::Right);
+        assert_eq!(directions.get(80), Direction::Right);
+        // wraps back round to the start
+        assert_eq!(directions.get(81), Direction::Left);
+    }
+
+    #[test]
+    fn test_long_node_names() {
+        let input = "LR\n\nSTARTA = (LOOP, STARTA)\nLOOP = (FINISHZ, LOOP)\nFINISHZ = (LOOP, LOOP)";
+        let puzzle_data = parse(input);
+        assert_eq!(puzzle_data.network.name(1), "LOOP");
+        assert_eq!(solve_part_b(&puzzle_data), 3);
+    }
+
+    #[test]
+    fn test_trace_events() {
+        let input = "LR\n\n11A = (11B, XXX)\n11B = (XXX, 11Z)\n11Z = (11B, XXX)\n22A = (22B, XXX)\n22B = (22C, 22C)\n22C = (22Z, 22Z)\n22Z = (22B, 22B)\nXXX = (XXX, XXX)";
+        let (steps, events) = record_events("ghost", || part_b(input));
+        assert_eq!(steps, 6);
+        for event in [
+            r#"{"event":"ghost","start":"11A","end":"11Z","steps":2,"cycle_length":2}"#,
+            r#"{"event":"ghost","start":"22A","end":"22Z","steps":3,"cycle_length":3}"#,
+        ] {
+            assert!(events.iter().any(|e| e == event), "no {}", event);
+        }
+    }
 
     #[test]
     fn test_part_a() {
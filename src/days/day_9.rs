@@ -1,50 +1,107 @@
-use advent_of_code_2023::to_i64;
+use crate::{checked, fold_lines, parallel, to_i64, trace_event};
 use itertools::Itertools;
+use std::io::BufRead;
+
+const TAKE_DIFFERENCES: &str = "taking differences";
+const EXTRAPOLATE: &str = "extrapolating a value";
+const SUM_NEXT_VALUES: &str = "summing the extrapolated values";
+
+/// the OASIS report, one history of values per line
+pub struct Report {
+    pub sequences: Vec<Vec<i64>>,
+}
 
 fn process_line(line: &str) -> Vec<i64> {
-    line.split_whitespace().map(to_i64).collect_vec()
+    // size the vec up front so each line is a single allocation
+    let mut values = Vec::with_capacity(line.split_ascii_whitespace().count());
+    values.extend(line.split_ascii_whitespace().map(to_i64));
+    values
 }
 
-fn process_sequence(seq: Vec<i64>) -> i64 {
+fn process_sequence(seq: &[i64]) -> i64 {
     let differences = seq
         .windows(2)
-        .map(|window| window[1] - window[0])
+        .map(|window| checked::sub(window[1], window[0], 9, TAKE_DIFFERENCES))
         .collect_vec();
 
     if differences.iter().all(|&x| x == 0) {
-        return *seq.last().unwrap();
+        *seq.last().unwrap()
     } else {
-        return seq.last().unwrap() + process_sequence(differences);
+        let next_difference = process_sequence(&differences);
+        checked::add(*seq.last().unwrap(), next_difference, 9, EXTRAPOLATE)
     }
 }
 
-pub fn part_a(input: &str) -> i64 {
+fn sum_next_values(sequences: &[Vec<i64>]) -> i64 {
+    let next_values = parallel::map(sequences, |seq| process_sequence(seq));
+    for (idx, next_value) in next_values.iter().enumerate() {
+        trace_event!("sequence", line = idx + 1, next_value = next_value);
+    }
+    checked::sum(next_values, 9, SUM_NEXT_VALUES)
+}
+
+/// sums the next value of each line's sequence as it's read, reversing each
+/// sequence first when `backwards` is set
+fn stream_next_values(reader: impl BufRead, backwards: bool) -> i64 {
+    let (_, sum) = fold_lines(reader, (0, 0i64), |(idx, sum), line| {
+        let mut sequence = process_line(line);
+        if backwards {
+            sequence.reverse();
+        }
+        let next_value = process_sequence(&sequence);
+        trace_event!("sequence", line = idx + 1, next_value = next_value);
+        let sum = checked::expect(sum.checked_add(next_value), 9, SUM_NEXT_VALUES);
+        (idx + 1, sum)
+    });
+    sum
+}
+
+pub fn parse(input: &str) -> Report {
     let sequences = input.lines().map(process_line).collect_vec();
-    let next_values = sequences.into_iter().map(process_sequence).collect_vec();
-    next_values.iter().sum()
+    Report { sequences }
 }
 
-pub fn part_b(input: &str) -> i64 {
-    let sequences = input
-        .lines()
-        .map(|x| process_line(x).into_iter().rev().collect_vec())
+pub fn solve_part_a(report: &Report) -> i64 {
+    sum_next_values(&report.sequences)
+}
+
+pub fn solve_part_b(report: &Report) -> i64 {
+    let sequences = report
+        .sequences
+        .iter()
+        .map(|x| x.iter().rev().copied().collect_vec())
         .collect_vec();
-    let next_values = sequences.into_iter().map(process_sequence).collect_vec();
-    next_values.iter().sum()
+    sum_next_values(&sequences)
+}
+
+pub fn stream_part_a(reader: impl BufRead) -> i64 {
+    stream_next_values(reader, false)
+}
+
+pub fn stream_part_b(reader: impl BufRead) -> i64 {
+    stream_next_values(reader, true)
+}
+
+pub fn part_a(input: &str) -> i64 {
+    solve_part_a(&parse(input))
+}
+
+pub fn part_b(input: &str) -> i64 {
+    solve_part_b(&parse(input))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use advent_of_code_2023::read_test_file;
+    use crate::read_test_file;
 
     #[test]
     fn test_process_sequence() {
         let input1 = vec![0, 3, 6, 9, 12, 15];
-        assert_eq!(process_sequence(input1), 18);
+        assert_eq!(process_sequence(&input1), 18);
 
         let input2 = vec![10, 13, 16, 21, 30, 45];
-        assert_eq!(process_sequence(input2), 68);
+        assert_eq!(process_sequence(&input2), 68);
     }
 
     #[test]
@@ -58,4 +115,11 @@ mod tests {
         let input = read_test_file(9);
         assert_eq!(part_b(&input), 2);
     }
+
+    #[test]
+    fn test_stream() {
+        let input = read_test_file(9);
+        assert_eq!(stream_part_a(input.as_bytes()), 114);
+        assert_eq!(stream_part_b(input.as_bytes()), 2);
+    }
 }
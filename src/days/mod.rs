@@ -1,3 +1,15 @@
+//! Every day exposes `parse`, which turns the raw input into that day's parsed
+//! input type, and `solve_part_a`/`solve_part_b` which take the parsed input.
+//! `part_a`/`part_b` do both in one go from the raw `&str`.
+//!
+//! ```
+//! use advent_of_code_2023::days::day_6;
+//!
+//! let race_sheet = day_6::parse("Time:      7  15   30\nDistance:  9  40  200");
+//! assert_eq!(race_sheet.races.len(), 3);
+//! assert_eq!(day_6::solve_part_a(&race_sheet), 288);
+//! ```
+
 pub mod day_1;
 pub mod day_2;
 pub mod day_3;
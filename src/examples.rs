@@ -0,0 +1,60 @@
+/// a puzzle's example input, compiled in, with the answers to both its parts
+pub struct Example {
+    pub day: u32,
+    pub input: &'static str,
+    pub part_a: &'static str,
+    pub part_b: &'static str,
+}
+
+macro_rules! example {
+    ($day:literal, $part_a:literal, $part_b:literal) => {
+        Example {
+            day: $day,
+            input: include_str!(concat!("../inputs/test/day_", $day, ".txt")),
+            part_a: $part_a,
+            part_b: $part_b,
+        }
+    };
+}
+
+/// Every solved day's example from `inputs/test`, embedded at compile time so
+/// neither the tests nor `selftest` depend on the working directory.
+pub const EXAMPLES: [Example; 9] = [
+    example!(1, "165", "159"),
+    example!(2, "8", "2286"),
+    example!(3, "4361", "467835"),
+    example!(4, "13", "30"),
+    example!(5, "35", "46"),
+    example!(6, "288", "71503"),
+    example!(7, "6440", "5905"),
+    example!(8, "2", "2"),
+    example!(9, "114", "2"),
+];
+
+pub fn example(day: u32) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|example| example.day == day)
+}
+
+impl Example {
+    pub fn answer(&self, part: char) -> &'static str {
+        match part {
+            'a' => self.part_a,
+            'b' => self.part_b,
+            _ => panic!("Unrecognised part [{}]", part),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        assert!(EXAMPLES.iter().map(|e| e.day).eq(1..=9));
+        let example = example(7).unwrap();
+        assert!(example.input.starts_with("32T3K 765"));
+        assert_eq!(example.answer('b'), "5905");
+        assert!(super::example(10).is_none());
+    }
+}
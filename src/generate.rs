@@ -0,0 +1,418 @@
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+
+const DIGIT_WORDS: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+const COLOURS: [&str; 3] = ["red", "green", "blue"];
+const SYMBOLS: [char; 10] = ['*', '#', '+', '$', '/', '@', '=', '%', '&', '-'];
+const CARDS: [char; 13] = [
+    '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
+];
+const NAME_CHARS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const CYCLE_PRIMES: [usize; 10] = [3, 5, 7, 11, 13, 17, 19, 23, 29, 31];
+
+pub struct GeneratorOptions {
+    /// how big the input should be, the unit depends on the day (lines, grid width, ...)
+    pub size: usize,
+    pub seed: u64,
+    /// day 4: the most winning numbers a single card is allowed to match
+    pub max_matches: usize,
+    /// day 8: how many ghosts (`..A` start nodes) the network should contain
+    pub ghosts: usize,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            size: 100,
+            seed: 0,
+            max_matches: 10,
+            ghosts: 6,
+        }
+    }
+}
+
+/// Builds a random but valid puzzle input for the given day. Inputs never end
+/// in a trailing newline as the solvers treat every `\n` as a row separator.
+pub fn generate(day: u32, options: &GeneratorOptions) -> String {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let size = options.size.max(1);
+    match day {
+        1 => day_1(&mut rng, size),
+        2 => day_2(&mut rng, size),
+        3 => day_3(&mut rng, size),
+        4 => day_4(&mut rng, size, options.max_matches),
+        5 => day_5(&mut rng, size),
+        6 => day_6(&mut rng, size),
+        7 => day_7(&mut rng, size),
+        8 => day_8(&mut rng, size, options.ghosts),
+        9 => day_9(&mut rng, size),
+        _ => panic!("No input generator exists for day [{}]", day),
+    }
+}
+
+/// size: number of calibration lines
+fn day_1(rng: &mut StdRng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let mut tokens = (0..rng.gen_range(2..8))
+                .map(|_| match rng.gen_range(0..3) {
+                    0 => rng.gen_range(1..10).to_string(),
+                    1 => DIGIT_WORDS.choose(rng).unwrap().to_string(),
+                    _ => (0..rng.gen_range(1..5))
+                        .map(|_| rng.gen_range(b'a'..=b'z') as char)
+                        .collect::<String>(),
+                })
+                .collect_vec();
+            // part a needs at least one real digit on every line
+            let digit_idx = rng.gen_range(0..=tokens.len());
+            tokens.insert(digit_idx, rng.gen_range(1..10).to_string());
+            tokens.concat()
+        })
+        .join("\n")
+}
+
+/// size: number of games
+fn day_2(rng: &mut StdRng, size: usize) -> String {
+    (1..=size)
+        .map(|id| {
+            let draws = (0..rng.gen_range(1..7))
+                .map(|_| {
+                    let colour_count = rng.gen_range(1..=COLOURS.len());
+                    COLOURS
+                        .choose_multiple(rng, colour_count)
+                        .collect_vec()
+                        .into_iter()
+                        .map(|colour| format!("{} {}", rng.gen_range(1..=20), colour))
+                        .join(", ")
+                })
+                .join("; ");
+            format!("Game {}: {}", id, draws)
+        })
+        .join("\n")
+}
+
+/// size: width and height of the engine schematic
+fn day_3(rng: &mut StdRng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let mut row = String::with_capacity(size);
+            while row.len() < size {
+                let remaining = size - row.len();
+                let previous_is_digit = row.ends_with(|c: char| c.is_ascii_digit());
+                match rng.gen_range(0..10) {
+                    0..=2 if !previous_is_digit => {
+                        let digits = rng.gen_range(1..=3).min(remaining);
+                        let number =
+                            rng.gen_range(10u32.pow(digits as u32 - 1)..10u32.pow(digits as u32));
+                        row.push_str(&number.to_string());
+                    }
+                    3 => row.push(*SYMBOLS.choose(rng).unwrap()),
+                    _ => row.push('.'),
+                }
+            }
+            row
+        })
+        .join("\n")
+}
+
+/// size: number of scratchcards
+fn day_4(rng: &mut StdRng, size: usize, max_matches: usize) -> String {
+    let winning_count = 10;
+    let drawn_count = 25;
+    let max_matches = max_matches.min(winning_count);
+    let id_width = size.to_string().len();
+
+    (1..=size)
+        .map(|id| {
+            let mut numbers = (1..100u32).collect_vec();
+            numbers.shuffle(rng);
+            let (winning, rest) = numbers.split_at(winning_count);
+            // never copy a card past the end of the table
+            let matches = rng.gen_range(0..=max_matches.min(size - id));
+            let mut drawn = winning[..matches].to_vec();
+            drawn.extend_from_slice(&rest[..drawn_count - matches]);
+            drawn.shuffle(rng);
+
+            let to_column = |nums: &[u32]| nums.iter().map(|n| format!("{:>2}", n)).join(" ");
+            format!(
+                "Card {:>width$}: {} | {}",
+                id,
+                to_column(winning),
+                to_column(&drawn),
+                width = id_width
+            )
+        })
+        .join("\n")
+}
+
+/// size: total number of seeds covered by the part b seed ranges
+fn day_5(rng: &mut StdRng, size: usize) -> String {
+    let seed_pairs = 10;
+    let seeds = (0..seed_pairs)
+        .map(|_| {
+            let start = rng.gen_range(0..u32::MAX as u64 / 2);
+            let length = rng.gen_range(1..=(size / seed_pairs).max(1));
+            format!("{} {}", start, length)
+        })
+        .join(" ");
+
+    let names = [
+        "seed",
+        "soil",
+        "fertilizer",
+        "water",
+        "light",
+        "temperature",
+        "humidity",
+        "location",
+    ];
+    let mappings = names
+        .windows(2)
+        .map(|pair| {
+            // cut the id space into adjacent, non-overlapping source ranges
+            // and leave some of them unmapped
+            let cuts = (0..rng.gen_range(5..40))
+                .map(|_| rng.gen_range(0..u32::MAX as u64))
+                .sorted()
+                .dedup()
+                .collect_vec();
+            let mut ranges = cuts
+                .iter()
+                .tuple_windows()
+                .filter_map(|(&source, &end)| {
+                    if rng.gen_bool(0.2) {
+                        return None;
+                    }
+                    let length = end - source;
+                    let destination = rng.gen_range(0..u32::MAX as u64 - length);
+                    Some(format!("{} {} {}", destination, source, length))
+                })
+                .collect_vec();
+            if ranges.is_empty() {
+                ranges.push(format!("0 0 {}", rng.gen_range(1..u32::MAX)));
+            }
+            ranges.shuffle(rng);
+            format!("{}-to-{} map:\n{}", pair[0], pair[1], ranges.join("\n"))
+        })
+        .join("\n\n");
+
+    format!("seeds: {}\n\n{}", seeds, mappings)
+}
+
+/// size: number of races, capped at 4 so the part b race still fits in a u64
+fn day_6(rng: &mut StdRng, size: usize) -> String {
+    // every race is beatable and uses fixed width numbers, which keeps the
+    // concatenated part b race beatable as well
+    let races = (0..size.min(4))
+        .map(|_| {
+            let time = rng.gen_range(64u64..100);
+            let distance = rng.gen_range(1000..time * time / 4);
+            (time, distance)
+        })
+        .collect_vec();
+    let times = races.iter().map(|(t, _)| format!("{:>4}", t)).join(" ");
+    let distances = races.iter().map(|(_, d)| format!("{:>4}", d)).join(" ");
+    format!("Time:     {}\nDistance: {}", times, distances)
+}
+
+/// size: number of hands, every hand is unique
+fn day_7(rng: &mut StdRng, size: usize) -> String {
+    let size = size.min(CARDS.len().pow(5));
+    let mut seen = HashSet::new();
+    let mut hands = Vec::with_capacity(size);
+    while hands.len() < size {
+        let cards = (0..5)
+            .map(|_| *CARDS.choose(rng).unwrap())
+            .collect::<String>();
+        if seen.insert(cards.clone()) {
+            hands.push(format!("{} {}", cards, rng.gen_range(1..=1000)));
+        }
+    }
+    hands.join("\n")
+}
+
+/// size: length of the direction list.
+///
+/// Every ghost walks a chain which reaches its `..Z` node after `k * size`
+/// steps and then loops back to the node after its start, so the `..Z` node
+/// is revisited every `k * size` steps. `k` is a distinct prime per ghost.
+/// Ghost 0 always walks from `AAA` to `ZZZ`.
+fn day_8(rng: &mut StdRng, size: usize, ghosts: usize) -> String {
+    let ghosts = ghosts.clamp(1, CYCLE_PRIMES.len());
+    let directions = (0..size)
+        .map(|_| if rng.gen_bool(0.5) { 'L' } else { 'R' })
+        .collect::<String>();
+
+    let mut primes = CYCLE_PRIMES.to_vec();
+    primes.shuffle(rng);
+    let chain_lengths = primes[..ghosts].iter().map(|p| p * size).collect_vec();
+    let decoy_count = size;
+
+    let middle_names = chain_lengths.iter().map(|l| l - 1).sum::<usize>() + decoy_count;
+    let middle_capacity = NAME_CHARS.len().pow(2) * (NAME_CHARS.len() - 2);
+    assert!(
+        middle_names <= middle_capacity,
+        "Network needs {} node names but only {} are available, reduce --size or --ghosts",
+        middle_names,
+        middle_capacity
+    );
+
+    let mut used_names = HashSet::from(["AAA".to_string(), "ZZZ".to_string()]);
+    let mut new_name = |rng: &mut StdRng, last: Option<u8>| loop {
+        let mut name = (0..3)
+            .map(|_| *NAME_CHARS.choose(rng).unwrap())
+            .collect_vec();
+        match last {
+            Some(l) => name[2] = l,
+            None if name[2] == b'A' || name[2] == b'Z' => continue,
+            None => {}
+        }
+        let name = String::from_utf8(name).unwrap();
+        if used_names.insert(name.clone()) {
+            return name;
+        }
+    };
+
+    let decoys = (0..decoy_count).map(|_| new_name(rng, None)).collect_vec();
+    // (name, left, right)
+    let mut nodes = Vec::<(String, String, String)>::new();
+
+    for (ghost, &chain_length) in chain_lengths.iter().enumerate() {
+        let (start, end) = if ghost == 0 {
+            ("AAA".to_string(), "ZZZ".to_string())
+        } else {
+            (new_name(rng, Some(b'A')), new_name(rng, Some(b'Z')))
+        };
+        let mut chain = vec![start];
+        chain.extend((1..chain_length).map(|_| new_name(rng, None)));
+        chain.push(end);
+
+        // the chain length is a multiple of the direction length, so the end
+        // node is always left using the first direction, just like the start
+        for step in 0..=chain_length {
+            let next = if step == chain_length { 1 } else { step + 1 };
+            let correct = chain[next].clone();
+            let decoy = decoys.choose(rng).unwrap().clone();
+            let (left, right) = match directions.as_bytes()[step % size] {
+                b'L' => (correct, decoy),
+                _ => (decoy, correct),
+            };
+            nodes.push((chain[step].clone(), left, right));
+        }
+    }
+
+    for decoy in &decoys {
+        let left = decoys.choose(rng).unwrap().clone();
+        let right = decoys.choose(rng).unwrap().clone();
+        nodes.push((decoy.clone(), left, right));
+    }
+    nodes.shuffle(rng);
+
+    let network = nodes
+        .iter()
+        .map(|(name, left, right)| format!("{} = ({}, {})", name, left, right))
+        .join("\n");
+    format!("{}\n\n{}", directions, network)
+}
+
+/// size: number of sequences
+fn day_9(rng: &mut StdRng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            // every sequence is a low degree polynomial so its differences reach zero
+            let coefficients = (0..rng.gen_range(1..=5))
+                .map(|_| rng.gen_range(-5i64..=5))
+                .collect_vec();
+            (0..21i64)
+                .map(|x| {
+                    coefficients
+                        .iter()
+                        .rev()
+                        .fold(0, |acc, &c| acc * x + c)
+                        .to_string()
+                })
+                .join(" ")
+        })
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_of_code_2023::days::*;
+
+    fn options(size: usize) -> GeneratorOptions {
+        GeneratorOptions {
+            size,
+            ..GeneratorOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        for day in 1..=9 {
+            assert_eq!(generate(day, &options(20)), generate(day, &options(20)));
+        }
+    }
+
+    #[test]
+    fn test_generated_inputs_are_solvable() {
+        for seed in 0..5 {
+            // keep the day 4 copy cascade small enough to fit in a u32
+            let opts = GeneratorOptions {
+                seed,
+                max_matches: 2,
+                ..options(40)
+            };
+            day_1::part_a(&generate(1, &opts));
+            day_1::part_b(&generate(1, &opts));
+            day_2::part_a(&generate(2, &opts));
+            day_2::part_b(&generate(2, &opts));
+            day_3::part_a(&generate(3, &opts));
+            day_3::part_b(&generate(3, &opts));
+            day_4::part_a(&generate(4, &opts));
+            day_4::part_b(&generate(4, &opts));
+            day_5::part_a(&generate(5, &opts));
+            day_5::part_b(&generate(5, &opts));
+            day_6::part_a(&generate(6, &opts));
+            day_6::part_b(&generate(6, &opts));
+            day_7::part_a(&generate(7, &opts));
+            day_7::part_b(&generate(7, &opts));
+            day_9::part_a(&generate(9, &opts));
+            day_9::part_b(&generate(9, &opts));
+        }
+    }
+
+    #[test]
+    fn test_day_4_match_counts() {
+        let opts = GeneratorOptions {
+            max_matches: 0,
+            ..options(30)
+        };
+        assert_eq!(day_4::part_a(&generate(4, &opts)), 0);
+        assert_eq!(day_4::part_b(&generate(4, &opts)), 30);
+    }
+
+    #[test]
+    fn test_day_8_cycle_structure() {
+        let opts = GeneratorOptions {
+            ghosts: 3,
+            ..options(10)
+        };
+        let input = generate(8, &opts);
+        assert_eq!(day_8::part_a(&input) % 10, 0);
+        // every ghost loops on a distinct prime multiple of the direction length
+        let cycle_product = day_8::part_b(&input) as usize / 10;
+        let matching_primes = CYCLE_PRIMES
+            .iter()
+            .combinations(3)
+            .filter(|c| c.iter().copied().product::<usize>() == cycle_product)
+            .count();
+        assert_eq!(matching_primes, 1);
+    }
+}
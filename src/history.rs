@@ -0,0 +1,405 @@
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Read, Write};
+use std::process::Command;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// the history written to the working directory unless the config says otherwise
+pub const DEFAULT_PATH: &str = "history.jsonl";
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// FNV-1a over the bytes of an input, which is plenty to tell inputs apart
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+fn to_hex(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+/// the hash of an input, ignoring any newlines it ends in as the solvers do
+pub fn input_hash(input: &str) -> String {
+    to_hex(fnv1a(FNV_OFFSET, input.trim_end_matches('\n').as_bytes()))
+}
+
+/// The hash of an input being read by a solver. Trailing newlines are held
+/// back until something follows them, so the hash matches `input_hash`.
+#[derive(Clone)]
+pub struct InputHasher {
+    hash: Rc<Cell<u64>>,
+    newlines: Rc<Cell<usize>>,
+}
+
+impl InputHasher {
+    pub fn new() -> InputHasher {
+        InputHasher {
+            hash: Rc::new(Cell::new(FNV_OFFSET)),
+            newlines: Rc::new(Cell::new(0)),
+        }
+    }
+
+    fn update(&self, bytes: &[u8]) {
+        let mut hash = self.hash.get();
+        for &byte in bytes {
+            if byte == b'\n' {
+                self.newlines.set(self.newlines.get() + 1);
+                continue;
+            }
+            for _ in 0..self.newlines.replace(0) {
+                hash = fnv1a(hash, b"\n");
+            }
+            hash = fnv1a(hash, &[byte]);
+        }
+        self.hash.set(hash);
+    }
+
+    /// wraps a reader so everything read through it is hashed
+    pub fn reader<R: BufRead>(&self, inner: R) -> HashingReader<R> {
+        HashingReader {
+            inner,
+            hasher: self.clone(),
+        }
+    }
+
+    pub fn hex(&self) -> String {
+        to_hex(self.hash.get())
+    }
+}
+
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: InputHasher,
+}
+
+impl<R: BufRead> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for HashingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // the buffer is still filled, so this hands back the bytes being consumed
+        if let Ok(buf) = self.inner.fill_buf() {
+            self.hasher.update(&buf[..amt]);
+        }
+        self.inner.consume(amt)
+    }
+}
+
+/// the checked out commit, marked dirty when there are uncommitted changes
+pub fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["describe", "--always", "--dirty"])
+        .output()
+        .ok()?;
+    let commit = String::from_utf8(output.stdout).ok()?;
+    (output.status.success() && !commit.trim().is_empty()).then(|| commit.trim().to_string())
+}
+
+/// A single part's answer from one run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Run {
+    /// seconds since the Unix epoch
+    pub timestamp: u64,
+    pub commit: Option<String>,
+    pub day: u32,
+    pub part: char,
+    pub answer: String,
+    pub micros: u64,
+    pub input_hash: String,
+    /// the day's options the run was given, e.g. `--bag 1 red`, empty for the puzzle itself
+    pub options: String,
+}
+
+impl Run {
+    pub fn new(
+        day: u32,
+        part: char,
+        answer: String,
+        elapsed: Duration,
+        input_hash: String,
+        options: String,
+    ) -> Run {
+        Run {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            commit: None,
+            day,
+            part,
+            answer,
+            micros: elapsed.as_micros() as u64,
+            input_hash,
+            options,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "timestamp": self.timestamp,
+            "commit": self.commit,
+            "day": self.day,
+            "part": self.part.to_string(),
+            "answer": self.answer,
+            "micros": self.micros,
+            "input_hash": self.input_hash,
+            "options": self.options,
+        })
+    }
+
+    fn from_json(line: &str) -> Option<Run> {
+        let value = serde_json::from_str::<Value>(line).ok()?;
+        Some(Run {
+            timestamp: value["timestamp"].as_u64()?,
+            commit: value["commit"].as_str().map(str::to_string),
+            day: u32::try_from(value["day"].as_u64()?).ok()?,
+            part: value["part"].as_str()?.chars().next()?,
+            answer: value["answer"].as_str()?.to_string(),
+            micros: value["micros"].as_u64()?,
+            input_hash: value["input_hash"].as_str()?.to_string(),
+            options: value["options"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+
+    /// runs that should give the same answer: same day, part, input and options
+    fn same_puzzle(&self, other: &Run) -> bool {
+        self.day == other.day
+            && self.part == other.part
+            && self.input_hash == other.input_hash
+            && self.options == other.options
+    }
+}
+
+/// adds runs to the end of the history, one JSON line each
+pub fn record(path: &str, runs: &[Run]) -> Result<(), String> {
+    let commit = git_commit();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("could not open history {}: {}", path, e))?;
+    let lines = runs
+        .iter()
+        .map(|run| {
+            let run = Run {
+                commit: commit.clone(),
+                ..run.clone()
+            };
+            format!("{}\n", run.to_json())
+        })
+        .collect::<String>();
+    file.write_all(lines.as_bytes())
+        .map_err(|e| format!("could not write history {}: {}", path, e))
+}
+
+/// every run recorded so far, oldest first, which is none if there's no history yet
+pub fn load(path: &str) -> Result<Vec<Run>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return Ok(Vec::new()),
+    };
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            Run::from_json(line).ok_or_else(|| format!("invalid history {} line {}", path, idx + 1))
+        })
+        .collect()
+}
+
+/// How a run compares to the last run of the same puzzle, if there was one.
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    /// the earlier answer when this run's differs from it
+    pub answer_was: Option<String>,
+    /// the percentage the runtime changed by
+    pub runtime: f64,
+}
+
+/// compares each run to the last run before it of the same puzzle
+pub fn changes(runs: &[Run]) -> Vec<Option<Change>> {
+    runs.iter()
+        .enumerate()
+        .map(|(idx, run)| {
+            let previous = runs[..idx].iter().rev().find(|r| r.same_puzzle(run))?;
+            Some(Change {
+                answer_was: (previous.answer != run.answer).then(|| previous.answer.clone()),
+                runtime: (run.micros as f64 - previous.micros as f64) * 100.0
+                    / previous.micros.max(1) as f64,
+            })
+        })
+        .collect()
+}
+
+/// Seconds since the epoch as a UTC date and time, using the days-to-civil
+/// conversion from Howard Hinnant's date algorithms.
+fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Prints the history of each day and part `filter` keeps, oldest run first,
+/// with each run's runtime against the last run of the same puzzle and a
+/// warning when its answer changed. Returns how many answers changed.
+pub fn print_history(
+    runs: &[Run],
+    filter: impl Fn(&Run) -> bool,
+    changed_only: bool,
+    json: bool,
+) -> usize {
+    let changes = changes(runs);
+    let mut puzzles = BTreeMap::<(u32, char, &str), Vec<(&Run, &Option<Change>)>>::new();
+    for (run, change) in runs.iter().zip(&changes).filter(|(run, _)| filter(run)) {
+        let key = (run.day, run.part, run.options.as_str());
+        puzzles.entry(key).or_default().push((run, change));
+    }
+
+    let mut changed_answers = 0;
+    for ((day, part, options), runs) in puzzles {
+        let mut header = Some(match options {
+            "" => format!("Day {} part {}", day, part),
+            options => format!("Day {} part {} with {}", day, part, options),
+        });
+        for (run, change) in runs {
+            let answer_was = change.as_ref().and_then(|c| c.answer_was.as_ref());
+            if answer_was.is_some() {
+                changed_answers += 1;
+            } else if changed_only {
+                continue;
+            }
+            if json {
+                let mut line = run.to_json();
+                line["answer_was"] = json!(answer_was);
+                println!("{}", line);
+                continue;
+            }
+            if let Some(header) = header.take() {
+                println!("{}", header);
+            }
+            let runtime = match change {
+                Some(change) => format!(
+                    "{:?} {:+.0}%",
+                    Duration::from_micros(run.micros),
+                    change.runtime
+                ),
+                None => format!("{:?}", Duration::from_micros(run.micros)),
+            };
+            println!(
+                "  {}  {:<14} {:>16}  {:<18} input {}",
+                format_timestamp(run.timestamp),
+                run.commit.as_deref().unwrap_or("unknown"),
+                run.answer,
+                runtime,
+                &run.input_hash[..run.input_hash.len().min(8)],
+            );
+            if let Some(answer_was) = answer_was {
+                println!("    answer changed from {} for the same input", answer_was);
+            }
+        }
+    }
+    changed_answers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(timestamp: u64, answer: &str, micros: u64, input_hash: &str) -> Run {
+        Run {
+            timestamp,
+            commit: Some("abc1234".to_string()),
+            day: 7,
+            part: 'b',
+            answer: answer.to_string(),
+            micros,
+            input_hash: input_hash.to_string(),
+            options: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_input_hash() {
+        let input = "32T3K 765\nT55J5 684\n\n";
+        let hasher = InputHasher::new();
+        let mut reader = hasher.reader(input.as_bytes());
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 0 {}
+        assert_eq!(hasher.hex(), input_hash(input));
+        assert_eq!(input_hash(input), input_hash("32T3K 765\nT55J5 684"));
+        assert_ne!(input_hash(input), input_hash("32T3K 765\n\nT55J5 684"));
+    }
+
+    #[test]
+    fn test_changes() {
+        let runs = vec![
+            run(0, "5905", 200, "aa"),
+            run(1, "5905", 100, "aa"),
+            run(2, "5911", 100, "bb"),
+            run(3, "5906", 150, "aa"),
+        ];
+        let changes = changes(&runs);
+        assert_eq!(changes[0], None);
+        assert_eq!(
+            changes[1],
+            Some(Change {
+                answer_was: None,
+                runtime: -50.0
+            })
+        );
+        // a different input is expected to give a different answer
+        assert_eq!(changes[2], None);
+        assert_eq!(
+            changes[3],
+            Some(Change {
+                answer_was: Some("5905".to_string()),
+                runtime: 50.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_run_json() {
+        let run = run(1700000000, "5905", 200, "aa");
+        assert_eq!(Run::from_json(&run.to_json().to_string()), Some(run));
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00");
+        assert_eq!(format_timestamp(1701388800 + 3661), "2023-12-01 01:01:01");
+    }
+}
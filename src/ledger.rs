@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+use toml::{Table, Value};
+
+/// the ledger read from the working directory unless the config says otherwise
+pub const DEFAULT_PATH: &str = "answers.toml";
+
+/// the right answers to a day's parts, and the hash of the input they're for
+#[derive(Debug, Default, PartialEq)]
+pub struct KnownAnswers {
+    pub input_hash: Option<String>,
+    pub answers: BTreeMap<char, String>,
+}
+
+/// how an answer compares to the ledger's
+#[derive(Debug, PartialEq)]
+pub enum Verification {
+    Right,
+    Wrong(String),
+    /// the ledger has no answer for the part
+    Unknown,
+    /// the ledger's answer is for a different input to the one solved
+    OtherInput,
+}
+
+/// The known right answers to each day's parts, kept as TOML with a table per
+/// day, e.g. `[7]` then `a = "6440"`. Profiles have a table of days each, e.g.
+/// `[alice.7]`, and each day may record the `hash` of the input it's for.
+#[derive(Debug, Default, PartialEq)]
+pub struct Ledger {
+    days: BTreeMap<(Option<String>, u32), KnownAnswers>,
+}
+
+fn parse_day(day: &str, key_path: &str) -> Result<u32, String> {
+    day.parse::<u32>()
+        .ok()
+        .filter(|d| (1..=25).contains(d))
+        .ok_or_else(|| format!("{} is not a day between 1 and 25", key_path))
+}
+
+fn parse_known_answers(value: &Value, key_path: &str) -> Result<KnownAnswers, String> {
+    let table = value
+        .as_table()
+        .ok_or_else(|| format!("{} must be a table of answers", key_path))?;
+    let mut known = KnownAnswers::default();
+    for (key, value) in table {
+        let part = match key.as_str() {
+            "hash" => {
+                let hash = value
+                    .as_str()
+                    .ok_or_else(|| format!("{}.hash must be a string", key_path))?;
+                known.input_hash = Some(hash.to_string());
+                continue;
+            }
+            "a" => 'a',
+            "b" => 'b',
+            p => return Err(format!("{} has unknown part [{}]", key_path, p)),
+        };
+        // answers too big for a TOML integer have to be written as strings
+        let answer = match value {
+            Value::String(s) => s.clone(),
+            Value::Integer(i) => i.to_string(),
+            v => {
+                return Err(format!(
+                    "{}.{} must be an answer, found {}",
+                    key_path, part, v
+                ))
+            }
+        };
+        known.answers.insert(part, answer);
+    }
+    Ok(known)
+}
+
+impl FromStr for Ledger {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let table = s.parse::<Table>().map_err(|e| e.to_string())?;
+        let mut ledger = Ledger::default();
+        for (key, value) in &table {
+            // days are numbers, so any other key is a profile
+            if key.starts_with(|c: char| c.is_ascii_digit()) {
+                let known = parse_known_answers(value, key)?;
+                ledger.days.insert((None, parse_day(key, key)?), known);
+                continue;
+            }
+            let days = value
+                .as_table()
+                .ok_or_else(|| format!("{} must be a table of days", key))?;
+            for (day, value) in days {
+                let key_path = format!("{}.{}", key, day);
+                let known = parse_known_answers(value, &key_path)?;
+                let day = parse_day(day, &key_path)?;
+                ledger.days.insert((Some(key.clone()), day), known);
+            }
+        }
+        Ok(ledger)
+    }
+}
+
+impl fmt::Display for Ledger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut table = Table::new();
+        for ((profile, day), known) in &self.days {
+            let mut day_table = known
+                .answers
+                .iter()
+                .map(|(part, answer)| (part.to_string(), Value::from(answer.clone())))
+                .collect::<Table>();
+            if let Some(hash) = &known.input_hash {
+                day_table.insert("hash".to_string(), Value::from(hash.clone()));
+            }
+            let days = match profile {
+                Some(profile) => table
+                    .entry(profile.clone())
+                    .or_insert_with(|| Value::Table(Table::new()))
+                    .as_table_mut()
+                    .unwrap(),
+                None => &mut table,
+            };
+            days.insert(day.to_string(), Value::Table(day_table));
+        }
+        write!(f, "{}", table)
+    }
+}
+
+impl Ledger {
+    /// reads the ledger at `path`, which starts out empty if there isn't one yet
+    pub fn load(path: &str) -> Result<Ledger, String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                Ledger::from_str(&contents).map_err(|e| format!("invalid ledger {}: {}", path, e))
+            }
+            Err(_) => Ok(Ledger::default()),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_string()).map_err(|e| format!("could not write {}: {}", path, e))
+    }
+
+    fn day(&self, profile: Option<&str>, day: u32) -> Option<&KnownAnswers> {
+        self.days.get(&(profile.map(str::to_string), day))
+    }
+
+    /// the right answer to a part, unless the ledger's is for another input
+    pub fn known(
+        &self,
+        profile: Option<&str>,
+        day: u32,
+        part: char,
+        input_hash: &str,
+    ) -> Option<&str> {
+        let known = self.day(profile, day)?;
+        if known
+            .input_hash
+            .as_ref()
+            .is_some_and(|hash| hash != input_hash)
+        {
+            return None;
+        }
+        known.answers.get(&part).map(|answer| answer.as_str())
+    }
+
+    pub fn verify(
+        &self,
+        profile: Option<&str>,
+        day: u32,
+        part: char,
+        input_hash: &str,
+        answer: &str,
+    ) -> Verification {
+        let Some(known) = self
+            .day(profile, day)
+            .filter(|k| k.answers.contains_key(&part))
+        else {
+            return Verification::Unknown;
+        };
+        match self.known(profile, day, part, input_hash) {
+            Some(expected) if expected == answer => Verification::Right,
+            Some(expected) => Verification::Wrong(expected.to_string()),
+            None if known.input_hash.is_some() => Verification::OtherInput,
+            None => Verification::Unknown,
+        }
+    }
+
+    /// Records the right answer to a part. An answer for a different input to
+    /// the day's other answers replaces them, as they no longer apply.
+    pub fn record(
+        &mut self,
+        profile: Option<&str>,
+        day: u32,
+        part: char,
+        answer: &str,
+        input_hash: Option<&str>,
+    ) {
+        let known = self
+            .days
+            .entry((profile.map(str::to_string), day))
+            .or_default();
+        if input_hash.is_some() && known.input_hash.as_deref() != input_hash {
+            known.answers.clear();
+            known.input_hash = input_hash.map(str::to_string);
+        }
+        known.answers.insert(part, answer.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEDGER: &str = r#"
+[7]
+a = 6440
+b = "5905"
+
+[alice.9]
+hash = "aa"
+a = "-3"
+"#;
+
+    #[test]
+    fn test_ledger_from_str() {
+        let ledger = Ledger::from_str(LEDGER).unwrap();
+        assert_eq!(ledger.known(None, 7, 'a', "any"), Some("6440"));
+        assert_eq!(ledger.known(None, 7, 'b', "any"), Some("5905"));
+        assert_eq!(ledger.known(Some("alice"), 9, 'a', "aa"), Some("-3"));
+        assert_eq!(ledger.known(Some("alice"), 9, 'a', "bb"), None);
+        assert_eq!(ledger.known(None, 9, 'a', "aa"), None);
+
+        assert_eq!(Ledger::from_str(&ledger.to_string()).unwrap(), ledger);
+        assert_eq!(
+            Ledger::from_str("[7]\nc = 1").unwrap_err(),
+            "7 has unknown part [c]"
+        );
+        assert_eq!(
+            Ledger::from_str("[bob.26]\na = 1").unwrap_err(),
+            "bob.26 is not a day between 1 and 25"
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let ledger = Ledger::from_str(LEDGER).unwrap();
+        let verify = |profile, day, hash, answer| ledger.verify(profile, day, 'a', hash, answer);
+        assert_eq!(verify(None, 7, "aa", "6440"), Verification::Right);
+        assert_eq!(
+            verify(None, 7, "aa", "6441"),
+            Verification::Wrong("6440".to_string())
+        );
+        assert_eq!(
+            verify(Some("alice"), 9, "bb", "-3"),
+            Verification::OtherInput
+        );
+        assert_eq!(
+            verify(Some("alice"), 7, "aa", "6440"),
+            Verification::Unknown
+        );
+    }
+
+    #[test]
+    fn test_record() {
+        let mut ledger = Ledger::from_str(LEDGER).unwrap();
+        ledger.record(Some("alice"), 9, 'b', "2", Some("aa"));
+        assert_eq!(ledger.known(Some("alice"), 9, 'a', "aa"), Some("-3"));
+
+        // a new input's answer replaces the ones for the old input
+        ledger.record(Some("alice"), 9, 'b', "5", Some("bb"));
+        assert_eq!(ledger.known(Some("alice"), 9, 'a', "bb"), None);
+        assert_eq!(ledger.known(Some("alice"), 9, 'b', "bb"), Some("5"));
+    }
+}
@@ -1,5 +1,12 @@
+use std::io::BufRead;
 use std::{env, fs};
 
+pub mod checked;
+pub mod days;
+pub mod examples;
+pub mod parallel;
+pub mod trace;
+
 pub fn to_u32(i: &str) -> u32 {
     i.trim().parse::<u32>().unwrap()
 }
@@ -12,12 +19,37 @@ pub fn to_i64(i: &str) -> i64 {
     i.trim().parse::<i64>().unwrap()
 }
 
+/// Folds `f` over every non-empty line of `reader` as it is read. A single
+/// line buffer is reused, so memory use doesn't grow with the input.
+pub fn fold_lines<T>(mut reader: impl BufRead, init: T, mut f: impl FnMut(T, &str) -> T) -> T {
+    let mut line = String::new();
+    let mut acc = init;
+    loop {
+        line.clear();
+        let bytes_read = reader
+            .read_line(&mut line)
+            .unwrap_or_else(|e| panic!("could not read input: {}", e));
+        if bytes_read == 0 {
+            return acc;
+        }
+        let line = line.trim_end_matches(['\n', '\r']);
+        if !line.is_empty() {
+            acc = f(acc, line);
+        }
+    }
+}
+
 pub fn read_file(day: u32) -> String {
     read_file_path(day, vec!["inputs"])
 }
 
+/// The day's example input, embedded from `inputs/test` when it was built, or
+/// read from there for a new day that isn't in `EXAMPLES` yet.
 pub fn read_test_file(day: u32) -> String {
-    read_file_path(day, vec!["inputs", "test"])
+    match examples::example(day) {
+        Some(example) => example.input.to_string(),
+        None => read_file_path(day, vec!["inputs", "test"]),
+    }
 }
 
 fn read_file_path(day: u32, path: Vec<&str>) -> String {
@@ -25,5 +57,15 @@ fn read_file_path(day: u32, path: Vec<&str>) -> String {
     let filename = format!("day_{}.txt", day);
     let filepath = path.iter().fold(cwd, |c, x| c.join(x));
     let f = fs::read_to_string(filepath.join(filename.clone()));
-    f.expect(&format!("could not open input file {}", filename))
+    f.unwrap_or_else(|_| panic!("could not open input file {}", filename))
+}
+
+/// Held by the tests that switch the global trace, checked and parallel flags
+/// or rely on them being off, as the tests otherwise run at once and see each
+/// other's flags.
+#[cfg(test)]
+pub(crate) fn lock_flags() -> std::sync::MutexGuard<'static, ()> {
+    static FLAGS: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    // a test that panics on purpose while holding it leaves nothing to undo
+    FLAGS.lock().unwrap_or_else(|e| e.into_inner())
 }
@@ -1,21 +1,81 @@
-extern crate argparse;
+use advent_of_code_2023::days::day_1::DigitMatcher;
+use advent_of_code_2023::days::day_2::Bag;
+use advent_of_code_2023::days::day_4::{CopyRules, Scoring};
+use advent_of_code_2023::days::day_6::{Acceleration, RaceModel};
+use advent_of_code_2023::days::day_7::JokerRules;
+use advent_of_code_2023::days::*;
+use advent_of_code_2023::examples::EXAMPLES;
+use advent_of_code_2023::{checked, parallel, trace};
+use bench::bench;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use cli::{BagArgs, Cli, Command, ConfigCommand, RunArgs};
+use config::Config;
+use generate::{generate, GeneratorOptions};
+use history::{InputHasher, Run};
+use ledger::{Ledger, Verification};
+use sandbox::{Limits, Stopped};
+use validate::validate;
+use visualise::visualise;
+use website::Verdict;
+mod allocator;
+mod bench;
+mod cli;
+mod config;
+mod generate;
+mod history;
+mod ledger;
+mod sandbox;
+mod scaffold;
+mod validate;
+mod visualise;
+mod watch;
+mod website;
+use rayon::prelude::*;
+use serde_json::json;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use std::{fmt, process};
 
-use advent_of_code_2023::read_file;
-use argparse::{ArgumentParser, Store};
-use days::*;
-mod days;
-use std::fmt;
+const IMPLEMENTED_DAYS: [u32; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+/// the days whose input is one item per line, so can be solved with `--stream`
+const STREAMED_DAYS: [u32; 5] = [1, 2, 4, 7, 9];
+
+/// Reports a mistake in the arguments the way clap does, with usage and exit
+/// code 2.
+fn usage_error(message: &str) -> ! {
+    Cli::command()
+        .error(ErrorKind::InvalidValue, message)
+        .exit()
+}
+
+/// reports a problem with a file, the config or the website and exits with code 1
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1)
+}
+
+/// checks there's a solver for the day
+fn implemented(day: u32) -> u32 {
+    if !IMPLEMENTED_DAYS.contains(&day) {
+        usage_error(&format!("day {} hasn't been solved yet", day));
+    }
+    day
+}
 
 #[derive(Debug)]
 enum ReturnType {
-    UInt32(u32),
+    UInt64(u64),
     Int64(i64),
     String(String),
 }
 
-impl From<u32> for ReturnType {
-    fn from(u: u32) -> Self {
-        Self::UInt32(u)
+impl From<u64> for ReturnType {
+    fn from(u: u64) -> Self {
+        Self::UInt64(u)
     }
 }
 
@@ -31,62 +91,1006 @@ impl From<String> for ReturnType {
     }
 }
 
+impl ReturnType {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            ReturnType::UInt64(value) => json!(value),
+            ReturnType::Int64(value) => json!(value),
+            ReturnType::String(value) => json!(value),
+        }
+    }
+}
+
+/// an answer read back from JSON, as a sandboxed solver gives it
+impl From<serde_json::Value> for ReturnType {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::String(s) => Self::String(s),
+            v => match (v.as_u64(), v.as_i64()) {
+                (Some(u), _) => Self::UInt64(u),
+                (_, Some(i)) => Self::Int64(i),
+                _ => Self::String(v.to_string()),
+            },
+        }
+    }
+}
+
 impl fmt::Display for ReturnType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ReturnType::UInt32(value) => write!(f, "{}", value),
+            ReturnType::UInt64(value) => write!(f, "{}", value),
             ReturnType::Int64(value) => write!(f, "{}", value),
             ReturnType::String(value) => write!(f, "{}", value),
         }
     }
 }
 
-fn main() {
-    let mut day = 0;
-    let mut part = ' ';
-    {
-        // this block limits scope of borrows by ap.refer() method
-        let mut ap = ArgumentParser::new();
-        ap.set_description("Run Advent of Code 2022.");
-        ap.refer(&mut day)
-            .add_option(&["-d", "--day"], Store, "Day to run");
-        ap.refer(&mut part)
-            .add_option(&["-p", "--part"], Store, "Part to run [a, b]");
-        ap.parse_args_or_exit();
-    }
-
-    if day < 1 || day > 25 {
-        panic!("--day parameter must be specified and be between `1` and `25` inclusive");
-    }
-
-    let valid_parts = vec!['a', 'b'];
-    if !valid_parts.contains(&part) {
-        panic!("--part parameter must be specified and be set either `a` or `b`");
-    }
-
-    let input = read_file(day);
-
-    let result: ReturnType = match (day, part) {
-        (1, 'a') => day_1::part_a(&input).into(),
-        (1, 'b') => day_1::part_b(&input).into(),
-        (2, 'a') => day_2::part_a(&input).into(),
-        (2, 'b') => day_2::part_b(&input).into(),
-        (3, 'a') => day_3::part_a(&input).into(),
-        (3, 'b') => day_3::part_b(&input).into(),
-        (4, 'a') => day_4::part_a(&input).into(),
-        (4, 'b') => day_4::part_b(&input).into(),
-        (5, 'a') => day_5::part_a(&input).into(),
-        (5, 'b') => day_5::part_b(&input).into(),
-        (6, 'a') => day_6::part_a(&input).into(),
-        (6, 'b') => day_6::part_b(&input).into(),
-        (7, 'a') => day_7::part_a(&input).into(),
-        (7, 'b') => day_7::part_b(&input).into(),
-        (8, 'a') => day_8::part_a(&input).into(),
-        (8, 'b') => day_8::part_b(&input).into(),
-        (9, 'a') => day_9::part_a(&input).into(),
-        (9, 'b') => day_9::part_b(&input).into(),
-        (_, _) => panic!("Unrecognised day [{}] part [{}]", day, part),
+/// one part's answer, how long it took and the trace events it emitted
+struct PartResult {
+    part: char,
+    result: ReturnType,
+    elapsed: Duration,
+    events: Vec<trace::Event>,
+}
+
+/// the parts solved in a run, with how long their shared parse took (None when streamed)
+struct Solved {
+    parse: Option<Duration>,
+    parts: Vec<PartResult>,
+}
+
+impl Solved {
+    /// the parse and every part together
+    fn total(&self) -> Duration {
+        self.parse.unwrap_or_default() + self.parts.iter().map(|p| p.elapsed).sum::<Duration>()
+    }
+}
+
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let value = f();
+    (value, start.elapsed())
+}
+
+fn solve_part(part: char, f: impl FnOnce() -> ReturnType) -> PartResult {
+    let (result, elapsed) = timed(f);
+    PartResult {
+        part,
+        result,
+        elapsed,
+        events: trace::take_events(),
+    }
+}
+
+/// Parses the input once and solves each of `parts` from what it parsed, so
+/// running both parts doesn't pay for the parse twice.
+fn solve_with<P>(
+    parts: &[char],
+    parse: impl FnOnce() -> P,
+    solve: impl Fn(&P, char) -> ReturnType,
+) -> Solved {
+    let (parsed, parse_time) = timed(parse);
+    Solved {
+        parse: Some(parse_time),
+        parts: parts
+            .iter()
+            .map(|&part| solve_part(part, || solve(&parsed, part)))
+            .collect(),
+    }
+}
+
+/// a part solved while the input is read, which can only happen once
+fn stream_with(part: char, solve: impl FnOnce(char) -> ReturnType) -> Solved {
+    Solved {
+        parse: None,
+        parts: vec![solve_part(part, || solve(part))],
+    }
+}
+
+macro_rules! solve_day {
+    ($day:ident, $parts:expr, $input:expr) => {
+        solve_with(
+            $parts,
+            || $day::parse($input),
+            |parsed, part| match part {
+                'a' => $day::solve_part_a(parsed).into(),
+                _ => $day::solve_part_b(parsed).into(),
+            },
+        )
+    };
+}
+
+fn solve_parts(day: u32, parts: &[char], input: &str) -> Solved {
+    match day {
+        1 => solve_day!(day_1, parts, input),
+        2 => solve_day!(day_2, parts, input),
+        3 => solve_day!(day_3, parts, input),
+        4 => solve_day!(day_4, parts, input),
+        5 => solve_day!(day_5, parts, input),
+        6 => solve_day!(day_6, parts, input),
+        7 => solve_day!(day_7, parts, input),
+        8 => solve_day!(day_8, parts, input),
+        9 => solve_day!(day_9, parts, input),
+        _ => panic!("Unrecognised day [{}]", day),
+    }
+}
+
+fn solve(day: u32, part: char, input: &str) -> ReturnType {
+    solve_parts(day, &[part], input).parts.remove(0).result
+}
+
+/// solves from a reader line by line, for the days whose input is one item per line
+fn solve_stream(day: u32, part: char, reader: impl BufRead) -> ReturnType {
+    match (day, part) {
+        (1, 'a') => day_1::stream_part_a(reader).into(),
+        (1, 'b') => day_1::stream_part_b(reader).into(),
+        (2, 'a') => day_2::stream_part_a(reader).into(),
+        (2, 'b') => day_2::stream_part_b(reader).into(),
+        (4, 'a') => day_4::stream_part_a(reader).into(),
+        (4, 'b') => day_4::stream_part_b(reader).into(),
+        (7, 'a') => day_7::stream_part_a(reader).into(),
+        (7, 'b') => day_7::stream_part_b(reader).into(),
+        (9, 'a') => day_9::stream_part_a(reader).into(),
+        (9, 'b') => day_9::stream_part_b(reader).into(),
+        (_, _) => panic!(
+            "Day [{}] can't be streamed, only days 1, 2, 4, 7 and 9 can",
+            day
+        ),
+    }
+}
+
+/// opens `--input` ("-" meaning stdin), falling back to the day's input in the config
+fn open_input(day: u32, input_path: Option<&str>, config: &Config) -> Box<dyn BufRead> {
+    let path = match input_path {
+        Some("-") => return Box::new(io::stdin().lock()),
+        Some(path) => path.to_string(),
+        None => config.input_path(day),
     };
+    let file =
+        File::open(&path).unwrap_or_else(|_| fail(&format!("could not open input file {}", path)));
+    Box::new(BufReader::new(file))
+}
+
+/// reads the whole of an input into memory
+fn read_input(reader: impl BufRead) -> String {
+    let input = io::read_to_string(reader)
+        .unwrap_or_else(|e| fail(&format!("could not read input: {}", e)));
+    // files written by hand or piped in usually end in a newline the solvers don't expect
+    input.trim_end_matches('\n').to_string()
+}
+
+/// day 1 part b reading numbers with the vocabulary in a `--digit-words` file
+fn solve_with_digit_words(reader: impl BufRead, stream: bool, path: &str) -> Solved {
+    let words = fs::read_to_string(path)
+        .unwrap_or_else(|_| fail(&format!("could not open digit words file {}", path)));
+    let matcher = DigitMatcher::from_str(&words)
+        .unwrap_or_else(|e| fail(&format!("invalid digit words file {}: {}", path, e)));
+    if stream {
+        return stream_with('b', |_| day_1::stream_part_b_with(reader, &matcher).into());
+    }
+    let input = read_input(reader);
+    solve_with(
+        &['b'],
+        || day_1::parse(&input),
+        |document, _| day_1::solve_part_b_with(document, &matcher).into(),
+    )
+}
+
+/// day 2 played with a `--bag` other than the puzzle's
+fn solve_with_bag(parts: &[char], reader: impl BufRead, stream: bool, bag: &Bag) -> Solved {
+    if stream {
+        return stream_with(parts[0], |part| match part {
+            'a' => day_2::stream_part_a_with(reader, bag).into(),
+            _ => day_2::stream_part_b_with(reader, bag).into(),
+        });
+    }
+    let input = read_input(reader);
+    solve_with(
+        parts,
+        || day_2::parse(&input),
+        |record, part| match part {
+            'a' => day_2::solve_part_a_with(record, bag).into(),
+            _ => day_2::solve_part_b_with(record, bag).into(),
+        },
+    )
+}
+
+/// day 4 scored with `--scoring` (part a) or with `--copy-cap` and `--wrap-copies` copy rules (part b)
+fn solve_scratchcards(
+    parts: &[char],
+    reader: impl BufRead,
+    stream: bool,
+    scoring: &Scoring,
+    rules: &CopyRules,
+) -> Solved {
+    if stream {
+        return stream_with(parts[0], |part| match part {
+            'a' => day_4::stream_part_a_with(reader, scoring).into(),
+            _ => day_4::stream_part_b_with(reader, rules).into(),
+        });
+    }
+    let input = read_input(reader);
+    solve_with(
+        parts,
+        || day_4::parse(&input),
+        |pile, part| match part {
+            'a' => day_4::solve_part_a_with(pile, scoring).into(),
+            _ => day_4::solve_part_b_with(pile, rules).into(),
+        },
+    )
+}
+
+/// Day 3 part a summing the numbers next to `symbol` (any symbol when None),
+/// or part b summing the ratios of `symbol`s touching `neighbours` numbers.
+fn solve_schematic_query(
+    parts: &[char],
+    input: &str,
+    symbol: Option<u8>,
+    neighbours: usize,
+) -> Solved {
+    solve_with(
+        parts,
+        || day_3::parse(input),
+        |schematic, part| match part {
+            'a' => day_3::sum_part_numbers(schematic, symbol).into(),
+            _ => day_3::sum_gear_ratios(schematic, symbol.unwrap_or(b'*'), neighbours).into(),
+        },
+    )
+}
 
-    println!("Result: {}", result)
+/// Prints each game's minimum bag, the games each of `bags` can play, and
+/// how many games are possible with how many of the bags.
+fn print_bag_queries(input: &str, bags: &[Bag]) {
+    let record = day_2::parse(input);
+    for game in &record.games {
+        println!("Game {} needs at least {}", game.id, game.minimum_bag());
+    }
+    for bag in bags {
+        let ids = day_2::playable_games(&record, bag);
+        println!(
+            "{} can play {} of {} games: {}",
+            bag,
+            ids.len(),
+            record.games.len(),
+            ids.iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+    let distribution = day_2::possibility_distribution(&record, bags);
+    for (count, games) in distribution.iter().enumerate() {
+        println!(
+            "Possible with {} of {} bags: {} games",
+            count,
+            bags.len(),
+            games
+        );
+    }
+}
+
+/// adds runs to the history, which is worth a warning but not failing the run over
+fn record_history(runs: &[Run], config: &Config) {
+    if let Err(e) = history::record(&config.history, runs) {
+        eprintln!("warning: {}", e);
+    }
+}
+
+/// how a part fared in `all`
+enum Outcome {
+    Solved(ReturnType, Duration),
+    /// left out by the config or for want of an input
+    Skipped,
+    /// went past its limits, or crashed while sandboxed
+    Stopped(Stopped),
+}
+
+/// a part `all` ran, by index into the implemented days, and how it fared
+type AllResult = (usize, u32, char, Outcome);
+
+/// Solves both parts of every implemented day on a thread pool of `jobs`
+/// threads (0 lets rayon pick), in day order. Parts the config skips and days
+/// without an input aren't run, and days with a timeout or memory limit are
+/// solved in a sandbox.
+fn solve_all(jobs: usize, config: &Config, inputs: &[Option<String>]) -> Vec<AllResult> {
+    let tasks = IMPLEMENTED_DAYS
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, &day)| [(idx, day, 'a'), (idx, day, 'b')])
+        .collect::<Vec<(usize, u32, char)>>();
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .unwrap();
+    pool.install(|| {
+        tasks
+            .par_iter()
+            .map(|&(idx, day, part)| {
+                let Some(input) = inputs[idx].as_deref().filter(|_| !config.skips(day, part))
+                else {
+                    return (idx, day, part, Outcome::Skipped);
+                };
+                let limits = Limits {
+                    timeout: config.timeout(day),
+                    memory_limit: config.memory_limit(day),
+                };
+                let outcome = match limits.any() {
+                    true => match sandbox::solve(day, part, input, limits) {
+                        Ok((result, elapsed)) => Outcome::Solved(result.into(), elapsed),
+                        Err(stopped) => Outcome::Stopped(stopped),
+                    },
+                    false => {
+                        let (result, elapsed) = timed(|| solve(day, part, input));
+                        Outcome::Solved(result, elapsed)
+                    }
+                };
+                (idx, day, part, outcome)
+            })
+            .collect::<Vec<AllResult>>()
+    })
+}
+
+/// Runs both parts of every implemented day and prints the results in day
+/// order. Parts the config skips and days without an input are listed but not
+/// run, and any that were stopped are listed with why, exiting with 1 once the
+/// rest have run.
+fn run_all(jobs: usize, config: &Config) {
+    let inputs = IMPLEMENTED_DAYS.map(|day| config.read_input(day).ok());
+    let start = Instant::now();
+    let results = solve_all(jobs, config, &inputs);
+
+    let mut runs = Vec::new();
+    let mut stopped = 0;
+    for (idx, day, part, outcome) in results {
+        if let (Outcome::Solved(result, elapsed), Some(input)) = (&outcome, &inputs[idx]) {
+            let answer = result.to_string();
+            let input_hash = history::input_hash(input);
+            runs.push(Run::new(
+                day,
+                part,
+                answer,
+                *elapsed,
+                input_hash,
+                String::new(),
+            ));
+        }
+        if let Outcome::Stopped(_) = outcome {
+            stopped += 1;
+        }
+        match (config.output.as_str(), outcome) {
+            ("json", Outcome::Solved(result, elapsed)) => println!(
+                "{}",
+                json!({
+                    "day": day,
+                    "part": part.to_string(),
+                    "result": result.to_json(),
+                    "micros": elapsed.as_micros() as u64,
+                })
+            ),
+            ("json", Outcome::Skipped) => println!(
+                "{}",
+                json!({"day": day, "part": part.to_string(), "skipped": true})
+            ),
+            ("json", Outcome::Stopped(why)) => println!(
+                "{}",
+                json!({"day": day, "part": part.to_string(), "stopped": why.kind(), "reason": why.to_string()})
+            ),
+            (_, Outcome::Solved(result, elapsed)) => {
+                println!("Day {} part {}: {} ({:?})", day, part, result, elapsed)
+            }
+            (_, Outcome::Skipped) => println!("Day {} part {}: skipped", day, part),
+            (_, Outcome::Stopped(why)) => println!("Day {} part {}: {}", day, part, why),
+        }
+    }
+    match config.output.as_str() {
+        "json" => println!(
+            "{}",
+            json!({"total_micros": start.elapsed().as_micros() as u64})
+        ),
+        _ => println!("Total: {:?}", start.elapsed()),
+    }
+    record_history(&runs, config);
+    if stopped > 0 {
+        process::exit(1);
+    }
+}
+
+/// Runs every profile's inputs like `all` and checks each answer against the
+/// profile's known answers for that input, exiting with 1 if any are wrong or
+/// were stopped.
+fn verify_profiles(jobs: usize, config: &Config) {
+    let profiles = config.profiles();
+    if profiles.is_empty() {
+        fail(&format!("no inputs found under {}", config.input_root));
+    }
+    let ledger = Ledger::load(&config.ledger).unwrap_or_else(|e| fail(&e));
+    let json = config.output == "json";
+    let (mut right, mut wrong, mut unknown, mut stopped) = (0, 0, 0, 0);
+    let mut runs = Vec::new();
+
+    for profile in &profiles {
+        let mut config = config.clone();
+        config.set_profile(profile.clone());
+        let inputs = IMPLEMENTED_DAYS.map(|day| config.read_input(day).ok());
+        if !json {
+            println!("Profile {}", profile.as_deref().unwrap_or("(default)"));
+        }
+
+        for (idx, day, part, outcome) in solve_all(jobs, &config, &inputs) {
+            let Some(input) = &inputs[idx] else {
+                continue;
+            };
+            let (result, elapsed) = match outcome {
+                Outcome::Solved(result, elapsed) => (result, elapsed),
+                Outcome::Skipped => {
+                    match json {
+                        true => println!(
+                            "{}",
+                            json!({"profile": profile, "day": day, "part": part.to_string(), "skipped": true})
+                        ),
+                        false => println!("  Day {} part {}: skipped", day, part),
+                    }
+                    continue;
+                }
+                Outcome::Stopped(why) => {
+                    stopped += 1;
+                    match json {
+                        true => println!(
+                            "{}",
+                            json!({"profile": profile, "day": day, "part": part.to_string(), "stopped": why.kind(), "reason": why.to_string()})
+                        ),
+                        false => println!("  Day {} part {}: {}", day, part, why),
+                    }
+                    continue;
+                }
+            };
+            let answer = result.to_string();
+            let input_hash = history::input_hash(input);
+            let verification = ledger.verify(profile.as_deref(), day, part, &input_hash, &answer);
+            let (verified, expected, note) = match &verification {
+                Verification::Right => ("right", None, "right".to_string()),
+                Verification::Wrong(expected) => (
+                    "wrong",
+                    Some(expected),
+                    format!("WRONG, the ledger has {}", expected),
+                ),
+                Verification::Unknown => ("unknown", None, "no known answer".to_string()),
+                Verification::OtherInput => (
+                    "other_input",
+                    None,
+                    "the known answer is for another input".to_string(),
+                ),
+            };
+            match verification {
+                Verification::Right => right += 1,
+                Verification::Wrong(_) => wrong += 1,
+                _ => unknown += 1,
+            }
+            match json {
+                true => println!(
+                    "{}",
+                    json!({
+                        "profile": profile,
+                        "day": day,
+                        "part": part.to_string(),
+                        "result": result.to_json(),
+                        "micros": elapsed.as_micros() as u64,
+                        "verified": verified,
+                        "expected": expected,
+                    })
+                ),
+                false => println!(
+                    "  Day {} part {}: {} ({:?}) {}",
+                    day, part, result, elapsed, note
+                ),
+            }
+            runs.push(Run::new(
+                day,
+                part,
+                answer,
+                elapsed,
+                input_hash,
+                String::new(),
+            ));
+        }
+    }
+
+    match json {
+        true => println!(
+            "{}",
+            json!({"profiles": profiles.len(), "right": right, "wrong": wrong, "unknown": unknown, "stopped": stopped})
+        ),
+        false => println!(
+            "{} profiles: {} right, {} wrong, {} without a known answer, {} stopped",
+            profiles.len(),
+            right,
+            wrong,
+            unknown,
+            stopped
+        ),
+    }
+    record_history(&runs, config);
+    if wrong > 0 || stopped > 0 {
+        process::exit(1);
+    }
+}
+
+/// the lines of `--bag` and `--bags`, skipping blanks and `#` comments, or
+/// `defaults` when neither is given
+fn bag_lines(args: &BagArgs, defaults: &[String]) -> Vec<String> {
+    if args.bags.is_empty() && args.bags_file.is_none() {
+        return defaults.to_vec();
+    }
+    let bags_file = match &args.bags_file {
+        Some(path) => fs::read_to_string(path)
+            .unwrap_or_else(|_| fail(&format!("could not open bags file {}", path))),
+        None => String::new(),
+    };
+    args.bags
+        .iter()
+        .map(|bag| bag.trim())
+        .chain(bags_file.lines().map(str::trim))
+        .filter(|bag| !bag.is_empty() && !bag.starts_with('#'))
+        .map(|bag| bag.to_string())
+        .collect()
+}
+
+fn parse_bags(lines: &[String]) -> Vec<Bag<'_>> {
+    lines
+        .iter()
+        .map(|line| Bag::parse(line).unwrap_or_else(|e| usage_error(&e)))
+        .collect()
+}
+
+/// Prints each part's trace events and answer. A single part prints just its
+/// answer, both parts print each answer's time and the combined time too.
+fn print_solved(day: u32, solved: &Solved, config: &Config) {
+    let json = config.output == "json";
+    for part in &solved.parts {
+        for event in &part.events {
+            match json {
+                true => println!("{}", event.to_json()),
+                false => println!("{}", event.to_text()),
+            }
+        }
+        let mut line =
+            json!({"day": day, "part": part.part.to_string(), "result": part.result.to_json()});
+        match (json, solved.parts.len()) {
+            (true, 1) => println!("{}", line),
+            (true, _) => {
+                line["micros"] = json!(part.elapsed.as_micros() as u64);
+                println!("{}", line)
+            }
+            (false, 1) => println!("Result: {}", part.result),
+            (false, _) => println!("Part {}: {} ({:?})", part.part, part.result, part.elapsed),
+        }
+    }
+    if solved.parts.len() > 1 {
+        let parse = solved.parse.unwrap_or_default();
+        match json {
+            true => println!(
+                "{}",
+                json!({
+                    "parse_micros": parse.as_micros() as u64,
+                    "total_micros": solved.total().as_micros() as u64,
+                })
+            ),
+            false => println!("Total: {:?} (parse {:?})", solved.total(), parse),
+        }
+    }
+}
+
+/// solves the parts asked for of a day, with whichever day's options were given
+fn run(args: &RunArgs, config: &Config) {
+    let day = implemented(args.day.day);
+    let parts = args.parts().chars();
+    if args.stream && !STREAMED_DAYS.contains(&day) {
+        usage_error(&format!(
+            "day {} can't be streamed, only days 1, 2, 4, 7 and 9 can",
+            day
+        ));
+    }
+    if args.stream && parts.len() > 1 {
+        usage_error("--stream reads the input as it solves, so needs a single --part");
+    }
+    let only_for = |applies: bool, message: &str| {
+        if !applies {
+            usage_error(message);
+        }
+    };
+    // the config's bags are only played by day 2, while --bag on another day is an error
+    let default_bags = match day {
+        2 => config.bags(),
+        _ => &[],
+    };
+    let bag_lines = bag_lines(&args.bags, default_bags);
+    let bags = parse_bags(&bag_lines);
+
+    if args.explain {
+        trace::enable();
+    }
+
+    let hasher = InputHasher::new();
+    let reader = || hasher.reader(open_input(day, args.input.as_deref(), config));
+    let solved = if let Some(digit_words) = &args.digit_words {
+        only_for(
+            day == 1 && parts == ['b'],
+            "--digit-words only applies to day 1 part b",
+        );
+        solve_with_digit_words(reader(), args.stream, digit_words)
+    } else if !bags.is_empty() {
+        only_for(day == 2, "--bag and --bags only apply to day 2");
+        if bags.len() > 1 {
+            usage_error("run plays a single --bag, use the bags command to compare several");
+        }
+        solve_with_bag(parts, reader(), args.stream, &bags[0])
+    } else if args.scoring.is_some() || args.copy_cap.is_some() || args.wrap_copies {
+        only_for(
+            day == 4,
+            "--scoring, --copy-cap and --wrap-copies only apply to day 4",
+        );
+        if args.wrap_copies && args.copy_cap.is_none() {
+            usage_error("--wrap-copies needs a --copy-cap, or cards winning each other never stop");
+        }
+        let scoring = match &args.scoring {
+            Some(scoring) => Scoring::from_str(scoring).unwrap_or_else(|e| usage_error(&e)),
+            None => Scoring::default(),
+        };
+        let rules = CopyRules {
+            cap: args.copy_cap,
+            wrap_around: args.wrap_copies,
+        };
+        solve_scratchcards(parts, reader(), args.stream, &scoring, &rules)
+    } else if args.acceleration.is_some() || args.max_speed.is_some() {
+        only_for(
+            day == 6,
+            "--acceleration and --max-speed only apply to day 6",
+        );
+        let model = RaceModel {
+            acceleration: match &args.acceleration {
+                Some(acceleration) => {
+                    Acceleration::from_str(acceleration).unwrap_or_else(|e| usage_error(&e))
+                }
+                None => RaceModel::default().acceleration,
+            },
+            max_speed: args.max_speed,
+        };
+        let input = read_input(reader());
+        solve_with(
+            parts,
+            || day_6::parse(&input),
+            |race_sheet, part| match part {
+                'a' => day_6::solve_part_a_with(race_sheet, &model).into(),
+                _ => day_6::solve_part_b_with(race_sheet, &model).into(),
+            },
+        )
+    } else if let Some(jokers) = &args.jokers {
+        only_for(
+            day == 7 && parts == ['b'],
+            "--jokers only applies to day 7 part b",
+        );
+        let rules = JokerRules::from_str(jokers).unwrap_or_else(|e| usage_error(&e));
+        if args.stream {
+            stream_with('b', |_| day_7::stream_part_b_with(reader(), &rules).into())
+        } else {
+            let input = read_input(reader());
+            solve_with(
+                parts,
+                || day_7::parse(&input),
+                |camel_cards, _| day_7::solve_part_b_with(camel_cards, &rules).into(),
+            )
+        }
+    } else if args.symbol.is_some() || args.neighbours.is_some() {
+        only_for(day == 3, "--symbol and --neighbours only apply to day 3");
+        let symbol = args.symbol.map(|symbol| match symbol {
+            s if s.is_ascii_punctuation() => s as u8,
+            s => usage_error(&format!("--symbol must be a single symbol, found [{}]", s)),
+        });
+        let neighbours = args.neighbours.unwrap_or(2);
+        solve_schematic_query(parts, &read_input(reader()), symbol, neighbours)
+    } else if args.stream {
+        stream_with(parts[0], |part| solve_stream(day, part, reader()))
+    } else {
+        solve_parts(day, parts, &read_input(reader()))
+    };
+
+    print_solved(day, &solved, config);
+
+    let runs = solved
+        .parts
+        .iter()
+        .map(|part| {
+            let (answer, options) = (part.result.to_string(), args.day_options());
+            Run::new(day, part.part, answer, part.elapsed, hasher.hex(), options)
+        })
+        .collect::<Vec<Run>>();
+    record_history(&runs, config);
+}
+
+/// prints every day, whether it's been solved and where its input is
+fn list_days(config: &Config) {
+    for day in 1..=25 {
+        let solved = IMPLEMENTED_DAYS.contains(&day);
+        let path = config.input_path(day);
+        let input = Path::new(&path).exists().then_some(path);
+        match config.output.as_str() {
+            "json" => println!("{}", json!({"day": day, "solved": solved, "input": input})),
+            _ => println!(
+                "Day {:>2}: {:<8} {}",
+                day,
+                if solved { "solved" } else { "unsolved" },
+                input.as_deref().unwrap_or("no input")
+            ),
+        }
+    }
+}
+
+/// Solves every day's embedded example, streamed as well for the days that
+/// can be, and checks each answer against the example's. Exits with 1 on any
+/// mismatch.
+fn selftest(config: &Config) {
+    let json = config.output == "json";
+    let (mut checked, mut mismatches) = (0, 0);
+    for example in &EXAMPLES {
+        let day = example.day;
+        let solved = solve_parts(day, &['a', 'b'], example.input);
+        let streamed = STREAMED_DAYS.contains(&day).then(|| {
+            ['a', 'b'].map(|part| (part, solve_stream(day, part, example.input.as_bytes())))
+        });
+        let results = solved
+            .parts
+            .into_iter()
+            .map(|p| (p.part, p.result, false))
+            .chain(
+                streamed
+                    .into_iter()
+                    .flatten()
+                    .map(|(part, result)| (part, result, true)),
+            );
+
+        for (part, result, stream) in results {
+            let answer = result.to_string();
+            let expected = example.answer(part);
+            checked += 1;
+            if answer != expected {
+                mismatches += 1;
+            }
+            let how = if stream { " (streamed)" } else { "" };
+            match (json, answer == expected) {
+                (true, _) => println!(
+                    "{}",
+                    json!({
+                        "day": day,
+                        "part": part.to_string(),
+                        "streamed": stream,
+                        "result": result.to_json(),
+                        "expected": expected,
+                        "ok": answer == expected,
+                    })
+                ),
+                (false, true) => println!("Day {} part {}{}: {} ok", day, part, how, answer),
+                (false, false) => println!(
+                    "Day {} part {}{}: {} MISMATCH, the example's answer is {}",
+                    day, part, how, answer, expected
+                ),
+            }
+        }
+    }
+    match json {
+        true => println!("{}", json!({"checked": checked, "mismatches": mismatches})),
+        false => println!("{} of {} answers match", checked - mismatches, checked),
+    }
+    if mismatches > 0 {
+        process::exit(1);
+    }
+}
+
+/// downloads a day's input to where the config expects it
+fn fetch(day: u32, force: bool, config: &Config) {
+    let path = config.input_path(day);
+    if Path::new(&path).exists() && !force {
+        fail(&format!(
+            "{} already exists, use --force to replace it",
+            path
+        ));
+    }
+    let input = website::fetch_input(config, day).unwrap_or_else(|e| fail(&e));
+    if let Some(parent) = Path::new(&path).parent() {
+        fs::create_dir_all(parent)
+            .unwrap_or_else(|e| fail(&format!("could not create {}: {}", parent.display(), e)));
+    }
+    fs::write(&path, input).unwrap_or_else(|e| fail(&format!("could not write {}: {}", path, e)));
+    println!("Saved the day {} input to {}", day, path);
+}
+
+/// sends an answer, solving for it first if one isn't given, exiting with 1 unless it's right
+fn submit(day: u32, part: char, answer: Option<String>, config: &Config) {
+    let answer = answer.unwrap_or_else(|| {
+        let input = config.read_input(day).unwrap_or_else(|e| fail(&e));
+        solve(day, part, &input).to_string()
+    });
+    println!("Submitting {} for day {} part {}", answer, day, part);
+    let verdict = website::submit_answer(config, day, part, &answer).unwrap_or_else(|e| fail(&e));
+    match &verdict {
+        Verdict::Correct => println!("That's the right answer"),
+        Verdict::Incorrect(Some(hint)) => println!("That's not the right answer, it's {}", hint),
+        Verdict::Incorrect(None) => println!("That's not the right answer"),
+        Verdict::TooSoon(Some(wait)) => println!("Answered too recently, wait {} more", wait),
+        Verdict::TooSoon(None) => println!("Answered too recently, wait a bit"),
+        Verdict::AlreadySolved => println!("Day {} part {} is already solved or locked", day, part),
+        Verdict::Unknown => println!("Couldn't read the verdict, check the puzzle page"),
+    }
+    if verdict != Verdict::Correct {
+        process::exit(1);
+    }
+    let mut ledger = Ledger::load(&config.ledger).unwrap_or_else(|e| fail(&e));
+    let input_hash = config
+        .read_input(day)
+        .ok()
+        .map(|input| history::input_hash(&input));
+    ledger.record(
+        config.profile.as_deref(),
+        day,
+        part,
+        &answer,
+        input_hash.as_deref(),
+    );
+    ledger.save(&config.ledger).unwrap_or_else(|e| fail(&e));
+    println!("Recorded the answer in {}", config.ledger);
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let mut overrides = cli.global.overrides();
+    match &cli.command {
+        Command::Bench { iterations, .. } => overrides.iterations = *iterations,
+        Command::All {
+            timeout,
+            memory_limit,
+            ..
+        } => {
+            overrides.timeout = *timeout;
+            overrides.memory_limit = *memory_limit;
+        }
+        _ => (),
+    }
+    let mut config = Config::load(&cli.global.config).unwrap_or_else(|e| fail(&e));
+    config.apply(&overrides).unwrap_or_else(|e| usage_error(&e));
+
+    if cli.global.parallel {
+        parallel::enable();
+    }
+    if cli.global.checked {
+        checked::enable();
+    }
+
+    match cli.command {
+        Command::Run(args) => run(&args, &config),
+        Command::All { jobs, profiles, .. } => match profiles {
+            true => verify_profiles(jobs, &config),
+            false => run_all(jobs, &config),
+        },
+        Command::Bench { day, memory, .. } => {
+            if memory && !cfg!(feature = "profile-alloc") {
+                usage_error("bench --memory needs a build with --features profile-alloc");
+            }
+            let day = implemented(day.day);
+            let input = config.read_input(day).unwrap_or_else(|e| fail(&e));
+            let result = bench(day, &input, config.iterations(day), memory);
+            match memory {
+                true => print!("{}", result),
+                false => println!("{}", result),
+            }
+        }
+        Command::Fetch { day, force } => fetch(day.day, force, &config),
+        Command::Submit { day, part, answer } => {
+            submit(implemented(day.day), part.as_char(), answer, &config)
+        }
+        Command::New { day } => scaffold::new_day(day.day).unwrap_or_else(|e| fail(&e)),
+        Command::Validate { day, input } => {
+            let day = implemented(day.day);
+            let input_file = match input.as_deref() {
+                Some("-") => "stdin".to_string(),
+                Some(path) => path.to_string(),
+                None => config.input_path(day),
+            };
+            let input = read_input(open_input(day, input.as_deref(), &config));
+            let problems = validate(day, &input);
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            if !problems.is_empty() {
+                println!("Found {} problem(s) in {}", problems.len(), input_file);
+                process::exit(1);
+            }
+            println!("{} is valid", input_file);
+        }
+        Command::List => list_days(&config),
+        Command::Solve {
+            day,
+            part,
+            memory_limit,
+        } => {
+            // set before the input is read, so it counts towards the limit and little
+            // allocated beforehand (just the command line) is freed under it
+            if let Some(mib) = memory_limit {
+                allocator::limit_memory(mib as usize * 1024 * 1024);
+            }
+            let day = implemented(day.day);
+            let input = read_input(BufReader::new(io::stdin()));
+            let (result, elapsed) = timed(|| solve(day, part.as_char(), &input));
+            let micros = elapsed.as_micros() as u64;
+            println!("{}", json!({"result": result.to_json(), "micros": micros}));
+        }
+        Command::Selftest => selftest(&config),
+        Command::Bags { bags, input } => {
+            let bag_lines = bag_lines(&bags, config.bags());
+            let bags = match parse_bags(&bag_lines) {
+                bags if bags.is_empty() => vec![Bag::default()],
+                bags => bags,
+            };
+            print_bag_queries(&read_input(open_input(2, input.as_deref(), &config)), &bags);
+        }
+        Command::Generate {
+            day,
+            size,
+            seed,
+            matches,
+            ghosts,
+        } => {
+            let defaults = GeneratorOptions::default();
+            let options = GeneratorOptions {
+                size: size.unwrap_or(defaults.size),
+                seed: seed.unwrap_or(defaults.seed),
+                max_matches: matches.unwrap_or(defaults.max_matches),
+                ghosts: ghosts.unwrap_or(defaults.ghosts),
+            };
+            print!("{}", generate(implemented(day.day), &options));
+        }
+        Command::Visualise { day, format } => {
+            match (day.day, format.as_str()) {
+                (3, "ansi" | "svg" | "") | (8, "dot" | "") => {}
+                (day, format) => usage_error(&format!(
+                    "no [{}] visualisation exists for day {}, day 3 has ansi and svg, day 8 has dot",
+                    format, day
+                )),
+            }
+            let input = config.read_input(day.day).unwrap_or_else(|e| fail(&e));
+            println!("{}", visualise(day.day, &input, &format));
+        }
+        Command::Watch {
+            day,
+            part,
+            interval,
+        } => watch::watch(
+            day.day,
+            part,
+            &cli.global.config,
+            &config,
+            Duration::from_millis(interval),
+        ),
+        Command::History { day, part, changes } => {
+            let runs = history::load(&config.history).unwrap_or_else(|e| fail(&e));
+            let changed = history::print_history(
+                &runs,
+                |run: &Run| {
+                    day.is_none_or(|d| run.day == d) && part.is_none_or(|p| run.part == p.as_char())
+                },
+                changes,
+                config.output == "json",
+            );
+            if changes && changed > 0 {
+                process::exit(1);
+            }
+        }
+        Command::Config(ConfigCommand::Show) => print!("{}", config),
+        Command::Completions { shell } => clap_complete::generate(
+            shell,
+            &mut Cli::command(),
+            "advent-of-code-2023",
+            &mut io::stdout(),
+        ),
+    }
 }
@@ -0,0 +1,62 @@
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Maps `f` over the independent units of a solver (games, ghosts, sequences),
+/// on rayon's thread pool when parallel solving is enabled. The results keep
+/// the order of `items` either way, so serial and parallel runs agree.
+pub fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    if is_enabled() {
+        items.par_iter().map(f).collect()
+    } else {
+        items.iter().map(f).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::{day_2, day_8, day_9};
+    use crate::read_test_file;
+
+    fn solve_both(day: u32) -> (i64, i64) {
+        let input = read_test_file(day);
+        match day {
+            2 => (day_2::part_a(&input) as i64, day_2::part_b(&input) as i64),
+            8 => (day_8::part_a(&input) as i64, day_8::part_b(&input)),
+            9 => (day_9::part_a(&input), day_9::part_b(&input)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let _flags = crate::lock_flags();
+        for day in [2, 8, 9] {
+            let serial = solve_both(day);
+            enable();
+            let parallel = solve_both(day);
+            disable();
+            assert_eq!(serial, parallel, "day {}", day);
+        }
+    }
+}
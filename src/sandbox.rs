@@ -0,0 +1,169 @@
+use advent_of_code_2023::{checked, parallel};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+use std::{env, fmt, thread};
+
+/// how often a sandboxed solver is checked on to see if it's finished
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// what a solver is allowed, None for no limit
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    pub timeout: Option<Duration>,
+    /// MiB of heap
+    pub memory_limit: Option<u32>,
+}
+
+impl Limits {
+    pub fn any(&self) -> bool {
+        self.timeout.is_some() || self.memory_limit.is_some()
+    }
+}
+
+/// why a sandboxed solver stopped without an answer
+#[derive(Debug, PartialEq)]
+pub enum Stopped {
+    Timeout(Duration),
+    /// went past the memory limit, in MiB
+    OutOfMemory(u32),
+    /// panicked or couldn't be run, with why
+    Crashed(String),
+}
+
+impl Stopped {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Stopped::Timeout(_) => "timeout",
+            Stopped::OutOfMemory(_) => "oom",
+            Stopped::Crashed(_) => "crashed",
+        }
+    }
+}
+
+impl fmt::Display for Stopped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stopped::Timeout(timeout) => write!(f, "TIMEOUT after {:?}", timeout),
+            Stopped::OutOfMemory(mib) => write!(f, "OOM over {} MiB", mib),
+            Stopped::Crashed(message) => write!(f, "CRASHED, {}", message),
+        }
+    }
+}
+
+/// the panic message in a crashed solver's stderr, or how it exited if there isn't one
+fn crash_message(stderr: &str, status: ExitStatus) -> String {
+    let mut lines = stderr.lines();
+    lines
+        .find(|line| line.contains("panicked at"))
+        .and_then(|_| lines.next())
+        .map(|message| message.trim().to_string())
+        .unwrap_or_else(|| status.to_string())
+}
+
+/// reads the whole of a child's pipe on a thread of its own, so it can't fill up
+fn read_pipe(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = String::new();
+        let _ = pipe.read_to_string(&mut output);
+        output
+    })
+}
+
+/// Solves a part in a child process of this binary, which is killed if it's
+/// still running at the timeout and fails any allocation past the memory
+/// limit. Gives the answer as JSON with how long the child took to find it.
+pub fn solve(
+    day: u32,
+    part: char,
+    input: &str,
+    limits: Limits,
+) -> Result<(Value, Duration), Stopped> {
+    let crashed = |e: std::io::Error| Stopped::Crashed(format!("could not run the solver: {}", e));
+    let mut command = Command::new(env::current_exe().map_err(crashed)?);
+    if parallel::is_enabled() {
+        command.arg("--parallel");
+    }
+    if checked::is_enabled() {
+        command.arg("--checked");
+    }
+    command.args([
+        "solve",
+        "--day",
+        &day.to_string(),
+        "--part",
+        &part.to_string(),
+    ]);
+    if let Some(memory_limit) = limits.memory_limit {
+        command.args(["--memory-limit", &memory_limit.to_string()]);
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(crashed)?;
+
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_string();
+    // the child stopping early closes the pipe, which is left to show in its status
+    thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let stdout = read_pipe(child.stdout.take().unwrap());
+    let stderr = read_pipe(child.stderr.take().unwrap());
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(crashed)? {
+            break status;
+        }
+        if let Some(timeout) = limits.timeout.filter(|&t| start.elapsed() >= t) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(Stopped::Timeout(timeout));
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        return Err(match limits.memory_limit {
+            // what Rust prints before aborting on a failed allocation, or the input
+            // being too big to read in
+            Some(mib)
+                if stderr.contains("memory allocation of") || stderr.contains("out of memory") =>
+            {
+                Stopped::OutOfMemory(mib)
+            }
+            _ => Stopped::Crashed(crash_message(&stderr, status)),
+        });
+    }
+    let solved = serde_json::from_str::<Value>(stdout.trim())
+        .map_err(|_| Stopped::Crashed(format!("unreadable answer [{}]", stdout.trim())))?;
+    let micros = solved["micros"].as_u64().unwrap_or_default();
+    Ok((solved["result"].clone(), Duration::from_micros(micros)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_message() {
+        let status = Command::new("false").status().unwrap();
+        let stderr = "\nthread 'main' panicked at src/days/day_5.rs:40:9:\nUnrecognised map [x]\nnote: run with `RUST_BACKTRACE=1`\n";
+        assert_eq!(crash_message(stderr, status), "Unrecognised map [x]");
+        assert_eq!(crash_message("", status), status.to_string());
+    }
+
+    #[test]
+    fn test_stopped_display() {
+        assert_eq!(
+            Stopped::Timeout(Duration::from_secs(30)).to_string(),
+            "TIMEOUT after 30s"
+        );
+        assert_eq!(Stopped::OutOfMemory(512).to_string(), "OOM over 512 MiB");
+        assert_eq!(Stopped::OutOfMemory(512).kind(), "oom");
+    }
+}
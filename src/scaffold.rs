@@ -0,0 +1,138 @@
+use std::fs;
+use std::path::Path;
+
+const DAYS_MOD: &str = "src/days/mod.rs";
+
+/// the starting point for a new day, following the layout every day shares
+const DAY_TEMPLATE: &str = r#"use itertools::Itertools;
+
+/// the puzzle input, one entry per line
+pub struct Puzzle {
+    pub lines: Vec<String>,
+}
+
+pub fn parse(input: &str) -> Puzzle {
+    let lines = input.lines().map(|line| line.to_string()).collect_vec();
+    Puzzle { lines }
+}
+
+pub fn solve_part_a(puzzle: &Puzzle) -> u64 {
+    todo!("solve part a from {} lines", puzzle.lines.len())
+}
+
+pub fn solve_part_b(puzzle: &Puzzle) -> u64 {
+    todo!("solve part b from {} lines", puzzle.lines.len())
+}
+
+pub fn part_a(input: &str) -> u64 {
+    solve_part_a(&parse(input))
+}
+
+pub fn part_b(input: &str) -> u64 {
+    solve_part_b(&parse(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_test_file;
+
+    #[test]
+    #[ignore = "the example answer isn't filled in yet"]
+    fn test_part_a() {
+        let input = read_test_file(DAY);
+        assert_eq!(part_a(&input), 0);
+    }
+
+    #[test]
+    #[ignore = "the example answer isn't filled in yet"]
+    fn test_part_b() {
+        let input = read_test_file(DAY);
+        assert_eq!(part_b(&input), 0);
+    }
+}
+"#;
+
+/// `mod_rs` with `pub mod day_N;` added among the other days in numeric order
+pub fn add_day_module(mod_rs: &str, day: u32) -> String {
+    let module = format!("pub mod day_{};", day);
+    let day_of = |line: &str| {
+        line.strip_prefix("pub mod day_")
+            .and_then(|rest| rest.strip_suffix(';'))
+            .and_then(|n| n.parse::<u32>().ok())
+    };
+    let mut lines = mod_rs.lines().collect::<Vec<&str>>();
+    if lines.contains(&module.as_str()) {
+        return mod_rs.to_string();
+    }
+    let position = lines
+        .iter()
+        .position(|line| day_of(line).is_some_and(|d| d > day))
+        .or_else(|| {
+            lines
+                .iter()
+                .rposition(|line| day_of(line).is_some())
+                .map(|p| p + 1)
+        })
+        .unwrap_or(lines.len());
+    lines.insert(position, &module);
+    lines.join("\n") + "\n"
+}
+
+/// Creates `src/days/day_N.rs` from the template, an empty example input and
+/// the module declaration, refusing to touch a day that already exists.
+pub fn new_day(day: u32) -> Result<(), String> {
+    let day_path = format!("src/days/day_{}.rs", day);
+    if Path::new(&day_path).exists() {
+        return Err(format!("{} already exists", day_path));
+    }
+    let mod_rs = fs::read_to_string(DAYS_MOD).map_err(|_| {
+        format!(
+            "could not open {}, run new from the repository root",
+            DAYS_MOD
+        )
+    })?;
+
+    let template = DAY_TEMPLATE.replace("(DAY)", &format!("({})", day));
+    fs::write(&day_path, template).map_err(|e| format!("could not write {}: {}", day_path, e))?;
+    println!("Created {}", day_path);
+
+    let test_path = format!("inputs/test/day_{}.txt", day);
+    if !Path::new(&test_path).exists() {
+        fs::create_dir_all("inputs/test")
+            .and_then(|_| fs::write(&test_path, ""))
+            .map_err(|e| format!("could not write {}: {}", test_path, e))?;
+        println!("Created {}, paste the puzzle's example into it", test_path);
+    }
+
+    fs::write(DAYS_MOD, add_day_module(&mod_rs, day))
+        .map_err(|e| format!("could not write {}: {}", DAYS_MOD, e))?;
+    println!("Added day_{} to {}", day, DAYS_MOD);
+    println!(
+        "To run it, add {} to IMPLEMENTED_DAYS and its parts to solve in src/main.rs",
+        day
+    );
+    println!(
+        "Once the example's answers are known, add it to EXAMPLES in src/examples.rs for selftest"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_day_module() {
+        let mod_rs = "//! docs\n\npub mod day_1;\npub mod day_2;\npub mod day_10;\n";
+        assert_eq!(
+            add_day_module(mod_rs, 3),
+            "//! docs\n\npub mod day_1;\npub mod day_2;\npub mod day_3;\npub mod day_10;\n"
+        );
+        assert_eq!(
+            add_day_module(mod_rs, 11),
+            "//! docs\n\npub mod day_1;\npub mod day_2;\npub mod day_10;\npub mod day_11;\n"
+        );
+        assert_eq!(add_day_module(mod_rs, 2), mod_rs);
+    }
+}
@@ -0,0 +1,142 @@
+use itertools::Itertools;
+pub use serde_json::json;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static EVENTS: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+
+/// A single step of a solver's reasoning, e.g. a number counted as a part
+/// number or a hand and the rank it was given.
+#[derive(Debug, PartialEq)]
+pub struct Event {
+    pub name: &'static str,
+    pub fields: Vec<(&'static str, Value)>,
+}
+
+impl Event {
+    pub fn to_text(&self) -> String {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(key, value)| match value {
+                Value::String(s) if !s.contains(' ') => format!("{}={}", key, s),
+                v => format!("{}={}", key, v),
+            })
+            .join(" ");
+        format!("{:<16} {}", self.name, fields)
+    }
+
+    pub fn to_json(&self) -> String {
+        // built by hand so the event name stays first and fields keep their order
+        let fields = self
+            .fields
+            .iter()
+            .map(|(key, value)| format!(",{}:{}", Value::from(*key), value))
+            .join("");
+        format!("{{\"event\":{}{}}}", Value::from(self.name), fields)
+    }
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn record(event: Event) {
+    EVENTS.lock().unwrap().push(event);
+}
+
+/// removes and returns every event recorded so far
+pub fn take_events() -> Vec<Event> {
+    std::mem::take(&mut *EVENTS.lock().unwrap())
+}
+
+/// Records a trace event when tracing is enabled. The field values are only
+/// evaluated when tracing is on, so a disabled trace costs a single flag check.
+///
+/// ```
+/// use advent_of_code_2023::trace_event;
+/// trace_event!("part_number", number = 467, line = 1);
+/// ```
+#[macro_export]
+macro_rules! trace_event {
+    ($name:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        if $crate::trace::is_enabled() {
+            $crate::trace::record($crate::trace::Event {
+                name: $name,
+                fields: vec![$((stringify!($key), $crate::trace::json!($value))),*],
+            });
+        }
+    };
+}
+
+/// Runs `f` with tracing on, giving back what it returned and the events named
+/// `name`. Other tests may be solving the same day meanwhile, so a test checks
+/// the events it expects are there rather than that they're all there is.
+#[cfg(test)]
+pub(crate) fn record_events<T>(name: &str, f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let _flags = crate::lock_flags();
+    take_events();
+    enable();
+    let value = f();
+    disable();
+    let events = take_events()
+        .into_iter()
+        .filter(|e| e.name == name)
+        .map(|e| e.to_json())
+        .collect_vec();
+    (value, events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_event() {
+        let _flags = crate::lock_flags();
+        let mut evaluated = false;
+        trace_event!(
+            "test_event",
+            value = {
+                evaluated = true;
+                1
+            }
+        );
+        assert!(!evaluated);
+
+        enable();
+        trace_event!(
+            "test_event",
+            number = 467,
+            cards = "KTJJT",
+            category = "two pair",
+            counted = true
+        );
+        disable();
+
+        let events = take_events()
+            .into_iter()
+            .filter(|e| e.name == "test_event")
+            .collect_vec();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].to_text(),
+            "test_event       number=467 cards=KTJJT category=\"two pair\" counted=true"
+        );
+        assert_eq!(
+            events[0].to_json(),
+            r#"{"event":"test_event","number":467,"cards":"KTJJT","category":"two pair","counted":true}"#
+        );
+    }
+}
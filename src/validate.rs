@@ -0,0 +1,614 @@
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+const DAY_5_CHAIN: [&str; 8] = [
+    "seed",
+    "soil",
+    "fertilizer",
+    "water",
+    "light",
+    "temperature",
+    "humidity",
+    "location",
+];
+
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+/// Walks a single line left to right. Every method that fails returns a
+/// `Problem` pointing at the column where the line stopped matching.
+#[derive(Clone, Copy)]
+struct LineReader<'a> {
+    line_no: usize,
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> LineReader<'a> {
+    fn new(line_no: usize, text: &'a str) -> Self {
+        LineReader {
+            line_no,
+            text,
+            pos: 0,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn column(&self) -> usize {
+        self.text[..self.pos].chars().count() + 1
+    }
+
+    fn problem(&self, message: String) -> Problem {
+        Problem {
+            line: self.line_no,
+            column: self.column(),
+            message,
+        }
+    }
+
+    fn found(&self) -> String {
+        match self.rest().chars().next() {
+            Some(c) => format!("'{}'", c),
+            None => "end of line".to_string(),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.rest().is_empty()
+    }
+
+    fn literal(&mut self, expected: &str) -> Result<(), Problem> {
+        if self.rest().starts_with(expected) {
+            self.pos += expected.len();
+            Ok(())
+        } else {
+            Err(self.problem(format!("expected \"{}\", found {}", expected, self.found())))
+        }
+    }
+
+    fn spaces(&mut self) -> usize {
+        let count = self.rest().len() - self.rest().trim_start_matches(' ').len();
+        self.pos += count;
+        count
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let taken = self.rest().len() - self.rest().trim_start_matches(&predicate).len();
+        let token = &self.text[self.pos..self.pos + taken];
+        self.pos += taken;
+        token
+    }
+
+    fn number(&mut self) -> Result<u64, Problem> {
+        let start = *self;
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            return Err(self.problem(format!("expected a number, found {}", self.found())));
+        }
+        digits
+            .parse::<u64>()
+            .map_err(|_| start.problem(format!("number {} is too large", digits)))
+    }
+
+    fn signed_number(&mut self) -> Result<i64, Problem> {
+        let start = *self;
+        let negative = self.rest().starts_with('-');
+        if negative {
+            self.pos += 1;
+        }
+        let value = i64::try_from(self.number()?)
+            .map_err(|_| start.problem("number is too large".to_string()))?;
+        Ok(if negative { -value } else { value })
+    }
+
+    /// space separated numbers up to the end of the line or the next non-number
+    fn number_list(&mut self) -> Result<Vec<(usize, u64)>, Problem> {
+        let mut numbers = Vec::new();
+        self.spaces();
+        while self.rest().starts_with(|c: char| c.is_ascii_digit()) {
+            let column = self.column();
+            numbers.push((column, self.number()?));
+            self.spaces();
+        }
+        Ok(numbers)
+    }
+
+    fn end(&self) -> Result<(), Problem> {
+        if self.is_done() {
+            Ok(())
+        } else {
+            Err(self.problem(format!("expected end of line, found {}", self.found())))
+        }
+    }
+}
+
+/// Checks an input against the format the given day's solvers expect and
+/// returns every problem found, in line order.
+pub fn validate(day: u32, input: &str) -> Vec<Problem> {
+    let raw_lines = input.split('\n').collect_vec();
+    let mut problems = check_whitespace(&raw_lines);
+    // whitespace problems are reported once above, not again by every day
+    let mut lines = raw_lines.iter().map(|l| l.trim_end()).collect_vec();
+    if lines.len() > 1 && lines.last() == Some(&"") {
+        lines.pop();
+    }
+
+    match day {
+        1 => day_1(&lines, &mut problems),
+        2 => each_line(&lines, &mut problems, day_2_line),
+        3 => day_3(&lines, &mut problems),
+        4 => day_4(&lines, &mut problems),
+        5 => day_5(&lines, &mut problems),
+        6 => day_6(&lines, &mut problems),
+        7 => day_7(&lines, &mut problems),
+        8 => day_8(&lines, &mut problems),
+        9 => each_line(&lines, &mut problems, day_9_line),
+        _ => panic!("No input format exists for day [{}]", day),
+    }
+
+    problems.sort_by_key(|p| (p.line, p.column));
+    problems
+}
+
+/// whitespace that sneaks in when copying an input breaks every day's parser
+fn check_whitespace(lines: &[&str]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        if let Some(pos) = line.find(['\r', '\t']) {
+            problems.push(Problem {
+                line: idx + 1,
+                column: line[..pos].chars().count() + 1,
+                message: "unexpected carriage return or tab".to_string(),
+            });
+        } else if line.len() != line.trim_end().len() {
+            problems.push(Problem {
+                line: idx + 1,
+                column: line.trim_end().chars().count() + 1,
+                message: "trailing whitespace".to_string(),
+            });
+        }
+    }
+    if lines.len() > 1 && lines.last() == Some(&"") {
+        problems.push(Problem {
+            line: lines.len(),
+            column: 1,
+            message: "input ends with a newline, remove it".to_string(),
+        });
+    }
+    problems
+}
+
+fn each_line(
+    lines: &[&str],
+    problems: &mut Vec<Problem>,
+    check: fn(&mut LineReader) -> Result<(), Problem>,
+) {
+    for (idx, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Err(problem) = check(&mut LineReader::new(idx + 1, line)) {
+            problems.push(problem);
+        }
+    }
+}
+
+fn day_1(lines: &[&str], problems: &mut Vec<Problem>) {
+    for (idx, line) in lines.iter().enumerate() {
+        let mut reader = LineReader::new(idx + 1, line);
+        reader.take_while(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+        if !reader.is_done() {
+            problems.push(reader.problem(format!(
+                "expected a lowercase letter or digit, found {}",
+                reader.found()
+            )));
+        } else if !line.chars().any(|c| c.is_ascii_digit()) {
+            problems.push(Problem {
+                line: idx + 1,
+                column: 1,
+                message: "line contains no digits".to_string(),
+            });
+        }
+    }
+}
+
+// example: "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green"
+fn day_2_line(reader: &mut LineReader) -> Result<(), Problem> {
+    reader.literal("Game ")?;
+    reader.number()?;
+    reader.literal(":")?;
+    loop {
+        let mut seen = HashSet::new();
+        loop {
+            reader.literal(" ")?;
+            reader.number()?;
+            reader.literal(" ")?;
+            let start = *reader;
+            // any colour is allowed, a bag just holds none of the ones it doesn't list
+            let colour = reader.take_while(|c| c.is_ascii_alphabetic());
+            if colour.is_empty() {
+                return Err(start.problem(format!("expected a colour, found {}", start.found())));
+            }
+            if !seen.insert(colour) {
+                return Err(start.problem(format!("{} drawn twice in one draw", colour)));
+            }
+            if reader.literal(",").is_err() {
+                break;
+            }
+        }
+        if reader.is_done() {
+            return Ok(());
+        }
+        reader.literal(";")?;
+    }
+}
+
+fn day_3(lines: &[&str], problems: &mut Vec<Problem>) {
+    let width = lines[0].chars().count();
+    for (idx, line) in lines.iter().enumerate() {
+        let mut reader = LineReader::new(idx + 1, line);
+        reader.take_while(|c| c.is_ascii_graphic());
+        if !reader.is_done() {
+            problems.push(reader.problem(format!("unexpected character {}", reader.found())));
+        } else if line.chars().count() != width {
+            problems.push(Problem {
+                line: idx + 1,
+                column: 1,
+                message: format!(
+                    "row is {} wide but the first row is {} wide",
+                    line.chars().count(),
+                    width
+                ),
+            });
+        }
+    }
+}
+
+// example: "Card   1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53"
+fn day_4(lines: &[&str], problems: &mut Vec<Problem>) {
+    let mut expected_counts = None;
+    for (idx, line) in lines.iter().enumerate() {
+        let mut reader = LineReader::new(idx + 1, line);
+        let mut check = || -> Result<(), Problem> {
+            reader.literal("Card")?;
+            if reader.spaces() == 0 {
+                return Err(reader.problem("expected a space after \"Card\"".to_string()));
+            }
+            reader.number()?;
+            reader.literal(":")?;
+            let winning = reader.number_list()?;
+            reader.literal("|")?;
+            let drawn = reader.number_list()?;
+            reader.end()?;
+
+            for list in [&winning, &drawn] {
+                let mut seen = HashSet::new();
+                if let Some(&(column, number)) = list.iter().find(|(_, n)| !seen.insert(*n)) {
+                    return Err(Problem {
+                        line: idx + 1,
+                        column,
+                        message: format!("number {} appears twice", number),
+                    });
+                }
+            }
+            let counts = (winning.len(), drawn.len());
+            match expected_counts {
+                None => expected_counts = Some(counts),
+                Some(expected) if expected != counts => {
+                    return Err(Problem {
+                        line: idx + 1,
+                        column: 1,
+                        message: format!(
+                            "card has {} winning and {} drawn numbers, the first card has {} and {}",
+                            counts.0, counts.1, expected.0, expected.1
+                        ),
+                    });
+                }
+                _ => {}
+            }
+            Ok(())
+        };
+        if let Err(problem) = check() {
+            problems.push(problem);
+        }
+    }
+}
+
+fn day_5(lines: &[&str], problems: &mut Vec<Problem>) {
+    let mut seeds = LineReader::new(1, lines[0]);
+    let seeds_result = seeds
+        .literal("seeds:")
+        .and_then(|_| seeds.number_list())
+        .and_then(|numbers| {
+            seeds.end()?;
+            if numbers.len() % 2 == 1 {
+                return Err(seeds.problem(
+                    "part b needs seeds in (start, length) pairs, found an odd count".to_string(),
+                ));
+            }
+            Ok(())
+        });
+    if let Err(problem) = seeds_result {
+        problems.push(problem);
+    }
+
+    let mut chain = DAY_5_CHAIN.windows(2);
+    let mut in_section = false;
+    for (idx, line) in lines.iter().enumerate().skip(1) {
+        let mut reader = LineReader::new(idx + 1, line);
+        let result = if line.is_empty() {
+            in_section = false;
+            Ok(())
+        } else if !in_section {
+            in_section = true;
+            match chain.next() {
+                Some(pair) => {
+                    let header = format!("{}-to-{} map:", pair[0], pair[1]);
+                    reader.literal(&header).and_then(|_| reader.end())
+                }
+                None => {
+                    Err(reader.problem("unexpected section after humidity-to-location".to_string()))
+                }
+            }
+        } else {
+            (0..3)
+                .map(|i| {
+                    if i > 0 {
+                        reader.literal(" ")?;
+                    }
+                    reader.number()
+                })
+                .collect::<Result<Vec<u64>, Problem>>()
+                .and_then(|_| reader.end())
+        };
+        if let Err(problem) = result {
+            problems.push(problem);
+        }
+    }
+    if let Some(pair) = chain.next() {
+        problems.push(Problem {
+            line: lines.len(),
+            column: 1,
+            message: format!("missing {}-to-{} map", pair[0], pair[1]),
+        });
+    }
+}
+
+fn day_6(lines: &[&str], problems: &mut Vec<Problem>) {
+    let mut counts = Vec::new();
+    for (idx, label) in ["Time:", "Distance:"].iter().enumerate() {
+        let Some(line) = lines.get(idx) else {
+            problems.push(Problem {
+                line: idx + 1,
+                column: 1,
+                message: format!("missing \"{}\" line", label),
+            });
+            continue;
+        };
+        let mut reader = LineReader::new(idx + 1, line);
+        match reader
+            .literal(label)
+            .and_then(|_| reader.number_list())
+            .and_then(|numbers| reader.end().map(|_| numbers.len()))
+        {
+            Ok(count) => counts.push(count),
+            Err(problem) => problems.push(problem),
+        }
+    }
+    if counts.len() == 2 && counts[0] != counts[1] {
+        problems.push(Problem {
+            line: 2,
+            column: 1,
+            message: format!("found {} times but {} distances", counts[0], counts[1]),
+        });
+    }
+    if lines.len() > 2 {
+        problems.push(Problem {
+            line: 3,
+            column: 1,
+            message: "expected only \"Time:\" and \"Distance:\" lines".to_string(),
+        });
+    }
+}
+
+// example: "32T3K 765"
+fn day_7(lines: &[&str], problems: &mut Vec<Problem>) {
+    let mut seen = HashMap::new();
+    for (idx, line) in lines.iter().enumerate() {
+        let mut reader = LineReader::new(idx + 1, line);
+        let cards = reader.take_while(|c| c != ' ');
+        let card_count = cards.chars().count();
+        for (column, card) in cards.chars().enumerate() {
+            if !"23456789TJQKA".contains(card) {
+                problems.push(Problem {
+                    line: idx + 1,
+                    column: column + 1,
+                    message: format!("'{}' is not a card [2-9, T, J, Q, K, A]", card),
+                });
+            }
+        }
+        if card_count != 5 {
+            problems.push(Problem {
+                line: idx + 1,
+                column: 1,
+                message: format!("hand has {} cards, expected 5", card_count),
+            });
+        }
+        if let Some(first_line) = seen.insert(cards, idx + 1) {
+            problems.push(Problem {
+                line: idx + 1,
+                column: 1,
+                message: format!("hand {} already appears on line {}", cards, first_line),
+            });
+        }
+        if let Err(problem) = reader
+            .literal(" ")
+            .and_then(|_| reader.number())
+            .and_then(|_| reader.end())
+        {
+            problems.push(problem);
+        }
+    }
+}
+
+// example: "BBB = (DDD, EEE)"
+fn day_8(lines: &[&str], problems: &mut Vec<Problem>) {
+    let mut directions = LineReader::new(1, lines[0]);
+    directions.take_while(|c| c == 'L' || c == 'R');
+    if lines[0].is_empty() {
+        problems.push(directions.problem("missing directions".to_string()));
+    } else if !directions.is_done() {
+        problems.push(directions.problem(format!(
+            "expected a direction [L, R], found {}",
+            directions.found()
+        )));
+    }
+    if lines.get(1) != Some(&"") {
+        problems.push(Problem {
+            line: 2,
+            column: 1,
+            message: "expected a blank line after the directions".to_string(),
+        });
+    }
+
+    let is_name_char = |c: char| c.is_ascii_alphanumeric();
+    let mut defined = HashMap::new();
+    let mut references = Vec::new();
+    for (idx, line) in lines.iter().enumerate().skip(2) {
+        let mut reader = LineReader::new(idx + 1, line);
+        let mut check = || -> Result<(), Problem> {
+            let name_column = reader.column();
+            let name = reader.take_while(is_name_char);
+            if name.is_empty() {
+                return Err(reader.problem(format!(
+                    "node names must be letters or digits, found \"{}\"",
+                    name
+                )));
+            }
+            if let Some(first_line) = defined.insert(name, idx + 1) {
+                return Err(Problem {
+                    line: idx + 1,
+                    column: name_column,
+                    message: format!("node {} already defined on line {}", name, first_line),
+                });
+            }
+            reader.literal(" = (")?;
+            for separator in [", ", ")"] {
+                let column = reader.column();
+                let reference = reader.take_while(is_name_char);
+                if reference.is_empty() {
+                    return Err(reader.problem(format!(
+                        "node names must be letters or digits, found \"{}\"",
+                        reference
+                    )));
+                }
+                references.push((idx + 1, column, reference));
+                reader.literal(separator)?;
+            }
+            reader.end()?;
+            Ok(())
+        };
+        if let Err(problem) = check() {
+            problems.push(problem);
+        }
+    }
+
+    for (line, column, reference) in references {
+        if !defined.contains_key(reference) {
+            problems.push(Problem {
+                line,
+                column,
+                message: format!("node {} is never defined", reference),
+            });
+        }
+    }
+}
+
+fn day_9_line(reader: &mut LineReader) -> Result<(), Problem> {
+    reader.signed_number()?;
+    while !reader.is_done() {
+        reader.literal(" ")?;
+        reader.signed_number()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_of_code_2023::read_test_file;
+
+    fn positions(problems: &[Problem]) -> Vec<(usize, usize)> {
+        problems.iter().map(|p| (p.line, p.column)).collect_vec()
+    }
+
+    #[test]
+    fn test_fixtures_are_valid() {
+        for day in 1..=9 {
+            assert_eq!(validate(day, &read_test_file(day)), vec![], "day {}", day);
+        }
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let problems = validate(9, "1 2 3 \n4 5 6\r\n");
+        assert_eq!(positions(&problems), vec![(1, 6), (2, 6), (3, 1)]);
+    }
+
+    #[test]
+    fn test_day_2() {
+        let input = "Game 1: 3 blue, 4 red; 1 red, 2 ?\nGame 2: 1 red 2 blue\nGame 3: 2 purple";
+        let problems = validate(2, input);
+        assert_eq!(positions(&problems), vec![(1, 33), (2, 14)]);
+    }
+
+    #[test]
+    fn test_day_5_chain_order() {
+        let input = "seeds: 1 2\n\nsoil-to-fertilizer map:\n1 2 3";
+        let problems = validate(5, input);
+        assert_eq!(positions(&problems), vec![(3, 1), (4, 1)]);
+        assert_eq!(
+            problems[0].message,
+            "expected \"seed-to-soil map:\", found 's'"
+        );
+    }
+
+    #[test]
+    fn test_day_7() {
+        let input = "32T3K 765\n32T3 684\n32X3K 28\n32T3K 1";
+        let problems = validate(7, input);
+        assert_eq!(positions(&problems), vec![(2, 1), (3, 3), (4, 1)]);
+    }
+
+    #[test]
+    fn test_day_8_undefined_nodes() {
+        let input = "LR\n\nAAA = (BBB, ZZZ)\nBBB = (AAA, CCC)\nZZZ = (ZZZ, ZZZ)";
+        let problems = validate(8, input);
+        assert_eq!(positions(&problems), vec![(4, 13)]);
+        assert_eq!(problems[0].message, "node CCC is never defined");
+    }
+
+    #[test]
+    fn test_day_8_long_node_names() {
+        let input = "LR\n\nSTARTA = (LOOP, STARTA)\nLOOP = (FINISHZ, LOOP)\nFINISHZ = (LOOP, LOOP)";
+        assert!(validate(8, input).is_empty());
+    }
+}
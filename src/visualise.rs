@@ -0,0 +1,323 @@
+use advent_of_code_2023::days::day_3::{self, Gear, Schematic};
+use advent_of_code_2023::days::day_8::{Direction, NodeId, PuzzleData};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+const RESET: &str = "\x1b[0m";
+const PART_NUMBER: &str = "\x1b[1;32m";
+const IGNORED_NUMBER: &str = "\x1b[31m";
+const GEAR: &str = "\x1b[1;35m";
+const SYMBOL: &str = "\x1b[33m";
+const DOT: &str = "\x1b[2m";
+
+const CELL_WIDTH: usize = 10;
+const CELL_HEIGHT: usize = 18;
+
+const GHOST_COLOURS: [&str; 8] = [
+    "#e41a1c", "#377eb8", "#4daf4a", "#984ea3", "#ff7f00", "#a65628", "#f781bf", "#999999",
+];
+
+/// Renders the given day's input in the requested format. Passing no format
+/// picks the day's default (ANSI for grids, DOT for graphs).
+pub fn visualise(day: u32, input: &str, format: &str) -> String {
+    match (day, format) {
+        (3, "ansi" | "") => day_3_ansi(input),
+        (3, "svg") => day_3_svg(input),
+        (8, "dot" | "") => day_8_dot(input),
+        (_, _) => panic!("No [{}] visualisation exists for day [{}]", format, day),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Cell {
+    PartNumber,
+    IgnoredNumber,
+    Gear,
+    Symbol,
+    Dot,
+}
+
+/// classifies every cell of the schematic, row by row
+fn classify_day_3(schematic: &Schematic, gears: &[Gear]) -> Vec<Vec<Cell>> {
+    let mut cells = schematic
+        .grid
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&c| if c == b'.' { Cell::Dot } else { Cell::Symbol })
+                .collect_vec()
+        })
+        .collect_vec();
+    for (id, number) in schematic.numbers.iter().enumerate() {
+        let cell = if schematic.is_part_number(id, None) {
+            Cell::PartNumber
+        } else {
+            Cell::IgnoredNumber
+        };
+        cells[number.y][number.start_x..=number.end_x].fill(cell);
+    }
+    for gear in gears {
+        cells[gear.y][gear.x] = Cell::Gear;
+    }
+    cells
+}
+
+fn day_3_ansi(input: &str) -> String {
+    let schematic = day_3::parse(input);
+    let grid = &schematic.grid;
+    let gears = schematic.gears(b'*', 2);
+    let cells = classify_day_3(&schematic, &gears);
+
+    let mut output = grid
+        .iter()
+        .zip(cells.iter())
+        .map(|(row, row_cells)| {
+            row.iter()
+                .zip(row_cells.iter())
+                .map(|(&c, cell)| {
+                    let colour = match cell {
+                        Cell::PartNumber => PART_NUMBER,
+                        Cell::IgnoredNumber => IGNORED_NUMBER,
+                        Cell::Gear => GEAR,
+                        Cell::Symbol => SYMBOL,
+                        Cell::Dot => DOT,
+                    };
+                    format!("{}{}{}", colour, c as char, RESET)
+                })
+                .join("")
+        })
+        .join("\n");
+
+    output.push_str(&format!(
+        "\n\n{}part number{}  {}not a part number{}  {}gear{}  {}symbol{}",
+        PART_NUMBER, RESET, IGNORED_NUMBER, RESET, GEAR, RESET, SYMBOL, RESET
+    ));
+    for gear in &gears {
+        output.push_str(&format!(
+            "\n{}gear{} at line {}, column {}: {} = {}",
+            GEAR,
+            RESET,
+            gear.y + 1,
+            gear.x + 1,
+            gear.numbers.iter().join(" * "),
+            gear.ratio()
+        ));
+    }
+    output
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn day_3_svg(input: &str) -> String {
+    let schematic = day_3::parse(input);
+    let grid = &schematic.grid;
+    let gears = schematic.gears(b'*', 2);
+    let cells = classify_day_3(&schematic, &gears);
+    let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+
+    let mut svg = vec![
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"14\">",
+            width * CELL_WIDTH,
+            grid.len() * CELL_HEIGHT
+        ),
+        "<rect width=\"100%\" height=\"100%\" fill=\"#0f0f23\"/>".to_string(),
+    ];
+
+    let rect = |x: usize, y: usize, cells_wide: usize, fill: &str, title: String| {
+        format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"><title>{}</title></rect>",
+            x * CELL_WIDTH,
+            y * CELL_HEIGHT,
+            cells_wide * CELL_WIDTH,
+            CELL_HEIGHT,
+            fill,
+            title
+        )
+    };
+    for (id, number) in schematic.numbers.iter().enumerate() {
+        let (fill, label) = if schematic.is_part_number(id, None) {
+            ("#2e7d32", "part number")
+        } else {
+            ("#8b1e1e", "not a part number")
+        };
+        svg.push(rect(
+            number.start_x,
+            number.y,
+            number.end_x - number.start_x + 1,
+            fill,
+            format!("{} ({})", number.value, label),
+        ));
+    }
+    for gear in &gears {
+        svg.push(rect(
+            gear.x,
+            gear.y,
+            1,
+            "#8e24aa",
+            format!(
+                "gear: {} = {}",
+                gear.numbers.iter().join(" * "),
+                gear.ratio()
+            ),
+        ));
+    }
+
+    for (y, (row, row_cells)) in grid.iter().zip(cells.iter()).enumerate() {
+        let text_y = y * CELL_HEIGHT + CELL_HEIGHT - 5;
+        for (x, (&c, cell)) in row.iter().zip(row_cells.iter()).enumerate() {
+            let fill = match cell {
+                Cell::Dot => continue,
+                Cell::Symbol => "#ffd54f",
+                _ => "#ffffff",
+            };
+            svg.push(format!(
+                "<text x=\"{}\" y=\"{}\" fill=\"{}\">{}</text>",
+                x * CELL_WIDTH + 1,
+                text_y,
+                fill,
+                escape_xml(&(c as char).to_string())
+            ));
+        }
+    }
+    svg.push("</svg>".to_string());
+    svg.join("\n")
+}
+
+fn direction_char(direction: Direction) -> char {
+    match direction {
+        Direction::Left => 'L',
+        Direction::Right => 'R',
+    }
+}
+
+/// Takes at least one step from `start`, stopping on the next end node.
+/// Returns the edges walked as (from, to, direction).
+fn walk_to_end(
+    start: NodeId,
+    start_idx: usize,
+    puzzle_data: &PuzzleData,
+) -> Vec<(NodeId, NodeId, char)> {
+    let network = &puzzle_data.network;
+    // a walk longer than this has to be going round a loop without an end node
+    let max_steps = network.len() * puzzle_data.directions.len() + 1;
+    let mut edges = Vec::new();
+    let mut current_node = start;
+    for idx in start_idx..start_idx + max_steps {
+        let direction = puzzle_data.directions.get(idx);
+        let next_node = network.next_node(current_node, direction);
+        edges.push((current_node, next_node, direction_char(direction)));
+        current_node = next_node;
+        if network.is_end_node(current_node) {
+            break;
+        }
+    }
+    edges
+}
+
+fn day_8_dot(input: &str) -> String {
+    let puzzle_data = PuzzleData::new(input);
+    let network = &puzzle_data.network;
+    let node_ids = (0..network.len())
+        .sorted_by_key(|&id| network.name(id))
+        .collect_vec();
+
+    let mut node_attributes = HashMap::<NodeId, String>::new();
+    let mut edge_attributes = HashMap::<(NodeId, char), String>::new();
+    for (ghost, start) in network.start_nodes().enumerate() {
+        let colour = GHOST_COLOURS[ghost % GHOST_COLOURS.len()];
+        let path = walk_to_end(start, 0, &puzzle_data);
+        let cycle = match path.last() {
+            Some(&(_, end, _)) => walk_to_end(end, path.len(), &puzzle_data),
+            None => vec![],
+        };
+
+        for &(from, _, direction) in &path {
+            edge_attributes.insert(
+                (from, direction),
+                format!("color=\"{}\", penwidth=2", colour),
+            );
+        }
+        for &(from, _, direction) in &cycle {
+            edge_attributes.insert(
+                (from, direction),
+                format!("color=\"{}\", penwidth=4, style=bold", colour),
+            );
+        }
+        node_attributes.insert(
+            start,
+            format!("fillcolor=\"{}\", shape=doublecircle", colour),
+        );
+        if let Some(&(_, end, _)) = path.last() {
+            node_attributes.insert(
+                end,
+                format!("fillcolor=\"{}\", shape=doubleoctagon", colour),
+            );
+        }
+    }
+
+    let mut dot = vec![
+        "digraph network {".to_string(),
+        "    node [shape=circle, style=filled, fillcolor=white];".to_string(),
+        "    edge [color=gray];".to_string(),
+    ];
+    for &id in &node_ids {
+        if let Some(attributes) = node_attributes.get(&id) {
+            dot.push(format!("    \"{}\" [{}];", network.name(id), attributes));
+        }
+    }
+    for &id in &node_ids {
+        for direction in [Direction::Left, Direction::Right] {
+            let label = direction_char(direction);
+            let attributes = match edge_attributes.get(&(id, label)) {
+                Some(highlight) => format!("label=\"{}\", {}", label, highlight),
+                None => format!("label=\"{}\"", label),
+            };
+            dot.push(format!(
+                "    \"{}\" -> \"{}\" [{}];",
+                network.name(id),
+                network.name(network.next_node(id, direction)),
+                attributes
+            ));
+        }
+    }
+    dot.push("}".to_string());
+    dot.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_of_code_2023::read_test_file;
+
+    #[test]
+    fn test_day_3_ansi() {
+        let output = visualise(3, "467.\n..*.\n.35.", "ansi");
+        assert!(output.starts_with(&format!("{}4{}", PART_NUMBER, RESET)));
+        assert!(output.contains("at line 2, column 3: 467 * 35 = 16345"));
+    }
+
+    #[test]
+    fn test_day_3_svg() {
+        let output = visualise(3, &read_test_file(3), "svg");
+        assert!(output.starts_with("<svg"));
+        assert!(output.contains("<title>114 (not a part number)</title>"));
+        assert!(output.contains("<title>gear: 467 * 35 = 16345</title>"));
+    }
+
+    #[test]
+    fn test_day_8_dot() {
+        let output = visualise(8, &read_test_file(8), "");
+        assert!(output.contains("\"AAA\" [fillcolor=\"#e41a1c\", shape=doublecircle];"));
+        assert!(output.contains("\"AAA\" -> \"CCC\" [label=\"R\", color=\"#e41a1c\", penwidth=2];"));
+        assert!(output.contains(
+            "\"ZZZ\" -> \"ZZZ\" [label=\"R\", color=\"#e41a1c\", penwidth=4, style=bold];"
+        ));
+        assert!(output.contains("\"AAA\" -> \"BBB\" [label=\"L\"];"));
+    }
+}
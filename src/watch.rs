@@ -0,0 +1,179 @@
+use crate::cli::Parts;
+use crate::config::Config;
+use crate::history;
+use crate::ledger::Ledger;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+use std::{env, fs, thread};
+
+/// the files a day's answers depend on
+fn watched_paths(day: u32, config: &Config) -> Vec<String> {
+    vec![
+        format!("src/days/day_{}.rs", day),
+        config.input_path(day),
+        format!("inputs/test/day_{}.txt", day),
+    ]
+}
+
+/// when each file was last changed, None when it's missing
+fn modified(paths: &[String]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// cargo, built the same way as this binary so the answers match a normal run
+fn cargo(subcommand: &str) -> Command {
+    let mut command = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    command.args([subcommand, "--quiet"]);
+    if !cfg!(debug_assertions) {
+        command.arg("--release");
+    }
+    command
+}
+
+/// reads the answer of each part out of the JSON lines `run --output json` prints
+fn read_answers(output: &str) -> BTreeMap<char, String> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter_map(|line| {
+            let part = line["part"].as_str()?.chars().next()?;
+            let answer = match &line["result"] {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                _ => return None,
+            };
+            Some((part, answer))
+        })
+        .collect()
+}
+
+/// an answer with how it compares to the last run's and to the ledger's
+fn describe(answer: &str, last: Option<&String>, known: Option<&str>) -> String {
+    let mut notes = Vec::new();
+    match last {
+        Some(last) if last == answer => notes.push("unchanged".to_string()),
+        Some(last) => notes.push(format!("was {}", last)),
+        None => {}
+    }
+    match known {
+        Some(known) if known == answer => notes.push("matches the ledger".to_string()),
+        Some(known) => notes.push(format!("the ledger has {}", known)),
+        None => {}
+    }
+    match notes.is_empty() {
+        true => answer.to_string(),
+        false => format!("{} ({})", answer, notes.join(", ")),
+    }
+}
+
+/// Runs the day's tests then its solver through cargo, so changes to the
+/// source are built first, and prints how the answers changed.
+fn run_once(
+    day: u32,
+    parts: Parts,
+    config_path: &str,
+    config: &Config,
+    last: &mut BTreeMap<char, String>,
+) {
+    let filter = format!("days::day_{}::", day);
+    match cargo("test").args(["--lib", &filter]).status() {
+        Ok(status) if status.success() => println!("Tests passed"),
+        Ok(_) => println!("Tests failed"),
+        Err(e) => println!("Could not run the tests: {}", e),
+    }
+
+    let day_arg = day.to_string();
+    let input = config.input_path(day);
+    let output = cargo("run")
+        .args(["--", "--config", config_path, "--output", "json", "run"])
+        .args(["--day", &day_arg, "--part", parts.name(), "--input", &input])
+        .output();
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            eprint!("{}", String::from_utf8_lossy(&output.stderr));
+            println!("The solver failed");
+            return;
+        }
+        Err(e) => {
+            println!("Could not run the solver: {}", e);
+            return;
+        }
+    };
+
+    let ledger = Ledger::load(&config.ledger).unwrap_or_else(|e| {
+        println!("{}", e);
+        Ledger::default()
+    });
+    let input_hash = history::input_hash(&config.read_input(day).unwrap_or_default());
+    let profile = config.profile.as_deref();
+    let answers = read_answers(&String::from_utf8_lossy(&output.stdout));
+    for (&part, answer) in &answers {
+        let known = ledger.known(profile, day, part, &input_hash);
+        let description = describe(answer, last.get(&part), known);
+        println!("Part {}: {}", part, description);
+    }
+    *last = answers;
+}
+
+/// Polls the day's source, input and example every `interval`, re-running the
+/// tests and solver when any of them change. Runs until interrupted.
+pub fn watch(day: u32, parts: Parts, config_path: &str, config: &Config, interval: Duration) {
+    let paths = watched_paths(day, config);
+    let mut seen = modified(&paths);
+    let mut last = BTreeMap::new();
+    println!("Watching {}, Ctrl-C to stop", paths.join(", "));
+    run_once(day, parts, config_path, config, &mut last);
+
+    loop {
+        thread::sleep(interval);
+        let now = modified(&paths);
+        let changed = paths
+            .iter()
+            .zip(seen.iter().zip(&now))
+            .filter(|(_, (before, after))| before != after)
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<&str>>();
+        if changed.is_empty() {
+            continue;
+        }
+        println!("\nChanged: {}", changed.join(", "));
+        seen = now;
+        run_once(day, parts, config_path, config, &mut last);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_answers() {
+        let output = "{\"day\":7,\"micros\":12,\"part\":\"a\",\"result\":6440}\n\
+                      {\"day\":7,\"micros\":30,\"part\":\"b\",\"result\":\"5905\"}\n\
+                      {\"parse_micros\":9,\"total_micros\":51}";
+        let answers = read_answers(output);
+        assert_eq!(answers.get(&'a').map(|s| s.as_str()), Some("6440"));
+        assert_eq!(answers.get(&'b').map(|s| s.as_str()), Some("5905"));
+        assert_eq!(answers.len(), 2);
+    }
+
+    #[test]
+    fn test_describe() {
+        let last = "6440".to_string();
+        assert_eq!(describe("6440", None, None), "6440");
+        assert_eq!(
+            describe("6440", Some(&last), Some("6440")),
+            "6440 (unchanged, matches the ledger)"
+        );
+        assert_eq!(
+            describe("6441", Some(&last), Some("6440")),
+            "6441 (was 6440, the ledger has 6440)"
+        );
+    }
+}
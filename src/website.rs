@@ -0,0 +1,141 @@
+use crate::config::Config;
+use std::time::Duration;
+
+const BASE_URL: &str = "https://adventofcode.com";
+/// adventofcode.com asks automated tools to say where they come from
+const USER_AGENT: &str = "github.com/mglewis/advent-of-code-2023";
+
+/// what adventofcode.com made of a submitted answer
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Correct,
+    /// wrong, with the site's hint if it gave one (e.g. "too high")
+    Incorrect(Option<String>),
+    /// answered too recently, with how long is left to wait
+    TooSoon(Option<String>),
+    /// the part has already been solved, or isn't unlocked yet
+    AlreadySolved,
+    /// a page the verdict couldn't be read from
+    Unknown,
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(30))
+        .build()
+}
+
+fn describe_error(error: ureq::Error, what: &str) -> String {
+    match error {
+        ureq::Error::Status(400, _) | ureq::Error::Status(500, _) => format!(
+            "could not {}, the session token may be invalid or expired",
+            what
+        ),
+        ureq::Error::Status(404, _) => format!("could not {}, the puzzle isn't out yet", what),
+        ureq::Error::Status(code, _) => format!("could not {}, the site answered {}", what, code),
+        ureq::Error::Transport(transport) => format!("could not {}: {}", what, transport),
+    }
+}
+
+/// downloads a day's input for the account the session token belongs to
+pub fn fetch_input(config: &Config, day: u32) -> Result<String, String> {
+    let token = config.session_token()?;
+    let url = format!("{}/{}/day/{}/input", BASE_URL, config.year, day);
+    let what = format!("fetch the input for day {}", day);
+    agent()
+        .get(&url)
+        .set("Cookie", &format!("session={}", token))
+        .call()
+        .map_err(|e| describe_error(e, &what))?
+        .into_string()
+        .map_err(|e| format!("could not {}: {}", what, e))
+}
+
+/// sends an answer to a day's part and reads the verdict off the page that comes back
+pub fn submit_answer(
+    config: &Config,
+    day: u32,
+    part: char,
+    answer: &str,
+) -> Result<Verdict, String> {
+    let token = config.session_token()?;
+    let url = format!("{}/{}/day/{}/answer", BASE_URL, config.year, day);
+    let level = match part {
+        'a' => "1",
+        _ => "2",
+    };
+    let what = format!("submit day {} part {}", day, part);
+    let page = agent()
+        .post(&url)
+        .set("Cookie", &format!("session={}", token))
+        .send_form(&[("level", level), ("answer", answer)])
+        .map_err(|e| describe_error(e, &what))?
+        .into_string()
+        .map_err(|e| format!("could not {}: {}", what, e))?;
+    Ok(read_verdict(&page))
+}
+
+/// the text between `start` and the next `end` following it
+fn between<'p>(page: &'p str, start: &str, end: &str) -> Option<&'p str> {
+    let (_, rest) = page.split_once(start)?;
+    rest.split_once(end).map(|(inside, _)| inside.trim())
+}
+
+/// reads the verdict out of the `<article>` the answer page puts it in
+pub fn read_verdict(page: &str) -> Verdict {
+    let article = between(page, "<article>", "</article>").unwrap_or(page);
+    if article.contains("That's the right answer") {
+        Verdict::Correct
+    } else if article.contains("That's not the right answer") {
+        let hint = ["too high", "too low"]
+            .iter()
+            .find(|hint| article.contains(*hint))
+            .map(|hint| hint.to_string());
+        Verdict::Incorrect(hint)
+    } else if article.contains("You gave an answer too recently") {
+        Verdict::TooSoon(between(article, "You have ", " left to wait").map(str::to_string))
+    } else if article.contains("You don't seem to be solving the right level") {
+        Verdict::AlreadySolved
+    } else {
+        Verdict::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(message: &str) -> String {
+        format!("<main>\n<article><p>{}</p></article>\n</main>", message)
+    }
+
+    #[test]
+    fn test_read_verdict() {
+        assert_eq!(
+            read_verdict(&page(
+                "That's the right answer!  You are one gold star closer."
+            )),
+            Verdict::Correct
+        );
+        assert_eq!(
+            read_verdict(&page(
+                "That's not the right answer; your answer is too high."
+            )),
+            Verdict::Incorrect(Some("too high".to_string()))
+        );
+        assert_eq!(
+            read_verdict(&page(
+                "You gave an answer too recently. You have 42s left to wait."
+            )),
+            Verdict::TooSoon(Some("42s".to_string()))
+        );
+        assert_eq!(
+            read_verdict(&page(
+                "You don't seem to be solving the right level.  Did you already complete it?"
+            )),
+            Verdict::AlreadySolved
+        );
+        assert_eq!(read_verdict("<html></html>"), Verdict::Unknown);
+    }
+}
@@ -0,0 +1,45 @@
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs};
+
+#[test]
+fn test_missing_input() {
+    let dir = env::temp_dir().join(format!("aoc-all-{}", std::process::id()));
+    let inputs = dir.join("inputs");
+    fs::create_dir_all(&inputs).unwrap();
+    // every example but day 5's
+    for day in (1..=9).filter(|&day| day != 5) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs/test")
+            .join(format!("day_{}.txt", day));
+        fs::copy(path, inputs.join(format!("day_{}.txt", day))).unwrap();
+    }
+    let config = format!(
+        "input_root = {:?}\nhistory = {:?}\nledger = {:?}\noutput = \"json\"\n",
+        inputs.to_string_lossy(),
+        dir.join("history.jsonl").to_string_lossy(),
+        dir.join("answers.toml").to_string_lossy(),
+    );
+    fs::write(dir.join("aoc.toml"), config).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_advent-of-code-2023"))
+        .args(["--config", &dir.join("aoc.toml").to_string_lossy(), "all"])
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(output.status.success());
+    let results = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .filter(|result| result["day"].is_u64())
+        .collect::<Vec<Value>>();
+    assert_eq!(results.len(), 18);
+    for result in &results {
+        match result["day"] == 5 {
+            true => assert_eq!(result["skipped"], true),
+            false => assert!(result["result"].is_number()),
+        }
+    }
+}
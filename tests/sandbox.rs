@@ -0,0 +1,66 @@
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs};
+
+/// Runs `all` over the examples with `day`'s input swapped for `input` and
+/// the limits in `day_config`, giving what it printed for each of the day's parts.
+fn run_all_limited(name: &str, day: u32, input: &str, day_config: &str) -> Vec<Value> {
+    let dir = env::temp_dir().join(format!("aoc-sandbox-{}-{}", name, std::process::id()));
+    let inputs = dir.join("inputs");
+    fs::create_dir_all(&inputs).unwrap();
+    for example in 1..=9 {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs/test")
+            .join(format!("day_{}.txt", example));
+        fs::copy(path, inputs.join(format!("day_{}.txt", example))).unwrap();
+    }
+    fs::write(inputs.join(format!("day_{}.txt", day)), input).unwrap();
+    let config = format!(
+        "input_root = {:?}\nhistory = {:?}\nledger = {:?}\noutput = \"json\"\n\n[days.{}]\n{}\n",
+        inputs.to_string_lossy(),
+        dir.join("history.jsonl").to_string_lossy(),
+        dir.join("answers.toml").to_string_lossy(),
+        day,
+        day_config
+    );
+    fs::write(dir.join("aoc.toml"), config).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_advent-of-code-2023"))
+        .args(["--config", &dir.join("aoc.toml").to_string_lossy(), "all"])
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    // a stopped part makes `all` exit with 1 once the rest have run
+    assert_eq!(output.status.code(), Some(1));
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .filter(|result| result["day"] == day)
+        .collect()
+}
+
+#[test]
+#[ignore = "runs the solvers in child processes, taking a few seconds"]
+fn test_timeout() {
+    // the walk from AAA never reaches ZZZ
+    let input = "L\n\nAAA = (AAA, ZZZ)\nZZZ = (ZZZ, ZZZ)";
+    let results = run_all_limited("timeout", 8, input, "timeout = 1\nskip = [\"b\"]");
+    assert_eq!(results[0]["part"], "a");
+    assert_eq!(results[0]["stopped"], "timeout");
+    assert_eq!(results[0]["reason"], "TIMEOUT after 1s");
+}
+
+#[test]
+#[ignore = "runs the solvers in child processes, taking a few seconds"]
+fn test_out_of_memory() {
+    // reading in 2 MiB of sequences goes well past the 1 MiB allowed
+    let input = vec!["0 3 6 9 12 15"; 150_000].join("\n");
+    let results = run_all_limited("oom", 9, &input, "memory_limit = 1");
+    for result in &results {
+        assert_eq!(result["stopped"], "oom");
+        assert_eq!(result["reason"], "OOM over 1 MiB");
+    }
+    assert_eq!(results.len(), 2);
+}